@@ -0,0 +1,2 @@
+ALTER TABLE jobs
+    ADD COLUMN run_at TIMESTAMPTZ NULL;
@@ -0,0 +1,4 @@
+-- Per-job override of the failure classification, e.g.
+-- {"user_error": "retryable", "timeout": "terminal"}
+ALTER TABLE jobs
+    ADD COLUMN failure_classification JSONB NULL;
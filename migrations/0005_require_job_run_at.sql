@@ -0,0 +1,13 @@
+-- `run_at` is the general "not before" timestamp for delayed, scheduled
+-- and retried jobs, not just retry backoff.
+UPDATE jobs
+    SET run_at = created_at
+    WHERE run_at IS NULL;
+
+ALTER TABLE jobs
+    ALTER COLUMN run_at SET DEFAULT now(),
+    ALTER COLUMN run_at SET NOT NULL;
+
+CREATE INDEX idx_jobs_queued_run_at
+    ON jobs (run_at)
+    WHERE state = 'queued';
@@ -0,0 +1,34 @@
+CREATE TABLE recurring_jobs (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+
+    cron_expression TEXT NOT NULL,
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+
+    payload_template JSONB NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 3,
+
+    missed_fire_policy TEXT NOT NULL DEFAULT 'fire_once' CHECK (
+        missed_fire_policy IN (
+            'skip',
+            'fire_once',
+            'catch_up_all'
+        )
+    ),
+
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    last_fire_time TIMESTAMPTZ NULL,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+ALTER TABLE jobs
+    ADD COLUMN recurring_job_id UUID NULL REFERENCES recurring_jobs(id) ON DELETE SET NULL,
+    ADD COLUMN fire_time TIMESTAMPTZ NULL;
+
+-- Exactly one job per definition and fire time, across all orchestrators.
+CREATE UNIQUE INDEX idx_jobs_recurring_fire_time
+    ON jobs (recurring_job_id, fire_time)
+    WHERE recurring_job_id IS NOT NULL;
@@ -0,0 +1,34 @@
+ALTER TABLE jobs
+    DROP CONSTRAINT jobs_state_check;
+
+ALTER TABLE jobs
+    ADD CONSTRAINT jobs_state_check CHECK (
+        state IN (
+            'blocked',
+            'queued',
+            'running',
+            'succeeded',
+            'failed',
+            'cancelled'
+        )
+    );
+
+ALTER TABLE jobs
+    ADD COLUMN dependency_failure_policy TEXT NOT NULL DEFAULT 'cancel' CHECK (
+        dependency_failure_policy IN (
+            'cancel',
+            'fail',
+            'run_anyway'
+        )
+    );
+
+CREATE TABLE job_dependencies (
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    depends_on UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+
+    PRIMARY KEY (job_id, depends_on),
+    CHECK (job_id <> depends_on)
+);
+
+CREATE INDEX idx_job_dependencies_depends_on
+    ON job_dependencies (depends_on);
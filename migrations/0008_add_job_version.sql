@@ -0,0 +1,4 @@
+-- Optimistic concurrency: every write bumps the version and is guarded
+-- by the version the writer last read.
+ALTER TABLE jobs
+    ADD COLUMN version BIGINT NOT NULL DEFAULT 0;
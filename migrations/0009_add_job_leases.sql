@@ -0,0 +1,7 @@
+ALTER TABLE jobs
+    ADD COLUMN lease_owner TEXT NULL,
+    ADD COLUMN lease_expires_at TIMESTAMPTZ NULL;
+
+CREATE INDEX idx_jobs_running_lease_expires_at
+    ON jobs (lease_expires_at)
+    WHERE state = 'running';
@@ -0,0 +1,13 @@
+CREATE TABLE recovery_runs (
+    id UUID PRIMARY KEY,
+    worker_id TEXT NOT NULL,
+
+    recovered_job_ids UUID[] NOT NULL,
+    skipped_job_ids UUID[] NOT NULL,
+
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_recovery_runs_started_at
+    ON recovery_runs (started_at);
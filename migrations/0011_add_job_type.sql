@@ -0,0 +1,7 @@
+-- Jobs and definitions created before job types existed keep running on
+-- whatever handler is registered for 'default'.
+ALTER TABLE jobs
+    ADD COLUMN job_type TEXT NOT NULL DEFAULT 'default';
+
+ALTER TABLE recurring_jobs
+    ADD COLUMN job_type TEXT NOT NULL DEFAULT 'default';
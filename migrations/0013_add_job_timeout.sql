@@ -0,0 +1,4 @@
+-- Per-job execution timeout; NULL falls back to the job type's default,
+-- then to JOB_TIMEOUT_SECS.
+ALTER TABLE jobs
+    ADD COLUMN timeout_ms BIGINT NULL CHECK (timeout_ms > 0);
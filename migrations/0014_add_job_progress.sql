@@ -0,0 +1,3 @@
+ALTER TABLE jobs
+    ADD COLUMN progress SMALLINT NULL CHECK (progress BETWEEN 0 AND 100),
+    ADD COLUMN checkpoint BYTEA NULL;
@@ -0,0 +1,20 @@
+CREATE TABLE job_logs (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+
+    level TEXT NOT NULL CHECK (
+        level IN (
+            'info',
+            'warn',
+            'error'
+        )
+    ),
+    message TEXT NOT NULL,
+
+    logged_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_job_logs_job_id
+    ON job_logs (job_id, id);
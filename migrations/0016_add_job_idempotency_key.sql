@@ -0,0 +1,17 @@
+-- Tenant that submitted the job; NULL for jobs submitted without one.
+ALTER TABLE jobs
+    ADD COLUMN tenant_id TEXT NULL;
+
+-- A resubmission with the same key, within the same tenant, returns the
+-- existing job until idempotency_expires_at. Expired keys are cleared
+-- before a new job may take them over.
+ALTER TABLE jobs
+    ADD COLUMN idempotency_key TEXT NULL,
+    ADD COLUMN idempotency_expires_at TIMESTAMPTZ NULL,
+    ADD CONSTRAINT jobs_idempotency_expiry CHECK (
+        (idempotency_key IS NULL) = (idempotency_expires_at IS NULL)
+    );
+
+CREATE UNIQUE INDEX idx_jobs_idempotency_key
+    ON jobs (COALESCE(tenant_id, ''), idempotency_key)
+    WHERE idempotency_key IS NOT NULL;
@@ -0,0 +1,9 @@
+-- At most one live (blocked, queued or running) job per unique key within
+-- a tenant. Finished jobs release the key.
+ALTER TABLE jobs
+    ADD COLUMN unique_key TEXT NULL;
+
+CREATE UNIQUE INDEX idx_jobs_unique_key
+    ON jobs (COALESCE(tenant_id, ''), unique_key)
+    WHERE unique_key IS NOT NULL
+      AND state IN ('blocked', 'queued', 'running');
@@ -0,0 +1,4 @@
+-- Named queue the job runs in; each queue may have its own concurrency
+-- limit on top of the global one. Existing jobs share 'default'.
+ALTER TABLE jobs
+    ADD COLUMN queue TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,139 @@
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::storage::repository::RepositoryError;
+
+/// Error returned by every endpoint, rendered as
+/// `{"error": {"code": ..., "message": ..., "field": ...}}`.
+///
+/// `code` is stable and meant for clients to match on; `message` is for
+/// people and may change.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{message}")]
+    InvalidRequest {
+        field: Option<&'static str>,
+        message: String,
+    },
+
+    #[error("job {0} not found")]
+    NotFound(Uuid),
+
+    /// The job is not in a state that allows the request.
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("internal error")]
+    Internal,
+}
+
+impl ApiError {
+    pub fn invalid(field: &'static str, message: impl Into<String>) -> Self {
+        Self::InvalidRequest {
+            field: Some(field),
+            message: message.into(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidRequest { .. } => StatusCode::BAD_REQUEST,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest { .. } => "invalid_request",
+            Self::NotFound(_) => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::Internal => "internal",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: ErrorDetail<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorDetail<'a> {
+    code: &'a str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<&'a str>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let field = match &self {
+            Self::InvalidRequest { field, .. } => *field,
+            _ => None,
+        };
+
+        let body = ErrorBody {
+            error: ErrorDetail {
+                code: self.code(),
+                message: self.to_string(),
+                field,
+            },
+        };
+
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+impl From<RepositoryError> for ApiError {
+    fn from(err: RepositoryError) -> Self {
+        match err {
+            RepositoryError::NotFound(job_id) => Self::NotFound(job_id),
+            RepositoryError::AlreadyExists => Self::Conflict("job already exists".to_string()),
+            RepositoryError::UnknownDependency => Self::invalid("depends_on", err.to_string()),
+            RepositoryError::StaleState { .. }
+            | RepositoryError::VersionConflict { .. }
+            | RepositoryError::LeaseLost { .. }
+            | RepositoryError::InvalidTransition(_)
+            | RepositoryError::IdempotencyConflict { .. }
+            | RepositoryError::UniqueKeyTaken => Self::Conflict(err.to_string()),
+            RepositoryError::Database(err) => {
+                error!(error = %err, "api request failed");
+                Self::Internal
+            }
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::InvalidRequest {
+            field: None,
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::InvalidRequest {
+            field: None,
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::InvalidRequest {
+            field: None,
+            message: rejection.body_text(),
+        }
+    }
+}
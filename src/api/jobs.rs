@@ -0,0 +1,446 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::error::ApiError;
+use crate::api::server::ApiState;
+use crate::dependencies::DependencyFailurePolicy;
+use crate::domain::failure::FailureKind;
+use crate::domain::job::{Debounce, Job, DEFAULT_JOB_TYPE};
+use crate::domain::state::JobState;
+use crate::logs::{JobLogLine, LogFollower};
+use crate::storage::repository::{Insertion, JobFilter, JobLogRepository, JobRepository};
+
+/// Page size of `GET /jobs` and `GET /jobs/:id/logs` when none is given.
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+/// Largest page size either endpoint accepts.
+pub const MAX_PAGE_SIZE: i64 = 500;
+/// Longest idempotency or unique key accepted.
+pub const MAX_KEY_LEN: usize = 255;
+/// Longest a `follow` request of `GET /jobs/:id/logs` waits for new lines.
+pub const FOLLOW_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Body of `POST /jobs`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmitJob {
+    pub job_type: Option<String>,
+    pub queue: Option<String>,
+    pub tenant_id: Option<String>,
+    pub payload: serde_json::Value,
+    #[serde(default)]
+    pub priority: i32,
+    pub max_attempts: Option<u32>,
+    /// Defaults to now.
+    pub run_at: Option<DateTime<Utc>>,
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub depends_on: Vec<Uuid>,
+    pub dependency_failure_policy: Option<DependencyFailurePolicy>,
+    /// Resubmitting with the same key, tenant, job type and payload
+    /// within the idempotency window returns the first job.
+    pub idempotency_key: Option<String>,
+    /// While a blocked, queued or running job of the tenant holds this
+    /// key, submitting returns that job instead, after applying `debounce`.
+    pub unique_key: Option<String>,
+    pub debounce: Option<Debounce>,
+}
+
+impl SubmitJob {
+    /// Validates the request and builds the job to insert.
+    ///
+    /// Rules:
+    /// 1. `job_type`, `queue` and `tenant_id`, when given, must not be
+    ///    blank.
+    /// 2. `max_attempts` must be at least 1; `timeout_ms` at least 1.
+    /// 3. A job may not depend on the same parent twice.
+    /// 4. `idempotency_key` and `unique_key`, when given, must not be blank
+    ///    or longer than `MAX_KEY_LEN`. The idempotency key expires
+    ///    `idempotency_ttl` from now.
+    /// 5. `debounce` requires `unique_key`.
+    pub fn into_job(
+        self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        idempotency_ttl: Duration,
+    ) -> Result<Job, ApiError> {
+        let job_type = match self.job_type {
+            Some(job_type) if job_type.trim().is_empty() => {
+                return Err(ApiError::invalid("job_type", "job_type must not be blank"));
+            }
+            Some(job_type) => job_type,
+            None => DEFAULT_JOB_TYPE.to_string(),
+        };
+
+        if self.queue.as_deref().is_some_and(|queue| queue.trim().is_empty()) {
+            return Err(ApiError::invalid("queue", "queue must not be blank"));
+        }
+
+        if self.tenant_id.as_deref().is_some_and(|tenant_id| tenant_id.trim().is_empty()) {
+            return Err(ApiError::invalid("tenant_id", "tenant_id must not be blank"));
+        }
+
+        validate_key("idempotency_key", self.idempotency_key.as_deref())?;
+        validate_key("unique_key", self.unique_key.as_deref())?;
+
+        if self.debounce.is_some() && self.unique_key.is_none() {
+            return Err(ApiError::invalid("debounce", "debounce requires unique_key"));
+        }
+
+        if self.max_attempts == Some(0) {
+            return Err(ApiError::invalid("max_attempts", "max_attempts must be at least 1"));
+        }
+
+        if self.timeout_ms == Some(0) {
+            return Err(ApiError::invalid("timeout_ms", "timeout_ms must be at least 1"));
+        }
+
+        let mut parents = self.depends_on.clone();
+        parents.sort();
+        parents.dedup();
+        if parents.len() != self.depends_on.len() {
+            return Err(ApiError::invalid("depends_on", "depends_on lists a job twice"));
+        }
+
+        let mut job = Job::new(id, self.payload, now)
+            .with_job_type(job_type)
+            .with_dependencies(
+                self.depends_on,
+                self.dependency_failure_policy.unwrap_or(DependencyFailurePolicy::Cancel),
+            );
+
+        job.priority = self.priority;
+        job.run_at = self.run_at.unwrap_or(now);
+        job.timeout = self.timeout_ms.map(Duration::from_millis);
+        if let Some(max_attempts) = self.max_attempts {
+            job.max_attempts = max_attempts;
+        }
+        if let Some(queue) = self.queue {
+            job = job.with_queue(queue);
+        }
+        if let Some(tenant_id) = self.tenant_id {
+            job = job.with_tenant(tenant_id);
+        }
+        if let Some(key) = self.idempotency_key {
+            let expires_at = chrono::Duration::from_std(idempotency_ttl)
+                .ok()
+                .and_then(|ttl| now.checked_add_signed(ttl))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC);
+            job = job.with_idempotency_key(key, expires_at);
+        }
+        if let Some(key) = self.unique_key {
+            job = job.with_unique_key(key, self.debounce.unwrap_or_default());
+        }
+
+        Ok(job)
+    }
+}
+
+fn validate_key(field: &'static str, key: Option<&str>) -> Result<(), ApiError> {
+    match key {
+        Some(key) if key.trim().is_empty() || key.len() > MAX_KEY_LEN => Err(ApiError::invalid(
+            field,
+            format!("{field} must be 1 to {MAX_KEY_LEN} bytes"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Query of `GET /jobs`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListJobs {
+    pub state: Option<JobState>,
+    pub job_type: Option<String>,
+    pub queue: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl ListJobs {
+    pub fn into_filter(self) -> Result<JobFilter, ApiError> {
+        let (limit, offset) = page(self.limit, self.offset)?;
+
+        Ok(JobFilter {
+            state: self.state,
+            job_type: self.job_type,
+            queue: self.queue,
+            limit,
+            offset,
+        })
+    }
+}
+
+/// Query of `GET /jobs/:id/logs`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReadLogs {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+    /// Wait for lines past `offset` instead of returning an empty page.
+    #[serde(default)]
+    pub follow: bool,
+}
+
+fn page(limit: Option<i64>, offset: Option<i64>) -> Result<(i64, i64), ApiError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::invalid(
+            "limit",
+            format!("limit must be between 1 and {MAX_PAGE_SIZE}"),
+        ));
+    }
+
+    let offset = offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(ApiError::invalid("offset", "offset must not be negative"));
+    }
+
+    Ok((limit, offset))
+}
+
+/// A job as returned by the API.
+#[derive(Debug, Serialize)]
+pub struct JobView {
+    pub id: Uuid,
+    pub job_type: String,
+    pub queue: String,
+    pub tenant_id: Option<String>,
+    pub state: JobState,
+    /// Changes on every write to the job.
+    pub version: i64,
+    pub payload: serde_json::Value,
+    pub priority: i32,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub run_at: DateTime<Utc>,
+    pub timeout_ms: Option<u64>,
+    pub failure: Option<FailureView>,
+    pub result: Option<serde_json::Value>,
+    pub progress: Option<u8>,
+    pub depends_on: Vec<Uuid>,
+    pub dependency_failure_policy: DependencyFailurePolicy,
+    pub idempotency_key: Option<String>,
+    pub unique_key: Option<String>,
+    pub lease_owner: Option<String>,
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    pub recurring_job_id: Option<Uuid>,
+    pub fire_time: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureView {
+    pub kind: FailureKind,
+    pub reason: String,
+}
+
+impl From<Job> for JobView {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id,
+            job_type: job.job_type,
+            queue: job.queue,
+            tenant_id: job.tenant_id,
+            state: job.state,
+            version: job.version,
+            payload: job.payload,
+            priority: job.priority,
+            attempt: job.attempt,
+            max_attempts: job.max_attempts,
+            run_at: job.run_at,
+            timeout_ms: job.timeout.map(|timeout| timeout.as_millis() as u64),
+            failure: job.failure.map(|failure| FailureView {
+                kind: failure.kind,
+                reason: failure.reason,
+            }),
+            result: job.result,
+            progress: job.progress,
+            depends_on: job.depends_on,
+            dependency_failure_policy: job.dependency_failure_policy,
+            idempotency_key: job.idempotency_key,
+            unique_key: job.unique_key,
+            lease_owner: job.lease_owner,
+            lease_expires_at: job.lease_expires_at,
+            recurring_job_id: job.recurring_job_id,
+            fire_time: job.fire_time,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobList {
+    pub jobs: Vec<JobView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogPage {
+    pub lines: Vec<JobLogLine>,
+    /// Offset to ask for next to continue reading.
+    pub next_offset: i64,
+    /// Set on a `follow` request once the job has finished and every line
+    /// before `next_offset` has been returned.
+    pub finished: bool,
+}
+
+pub async fn submit_job<R>(
+    State(state): State<ApiState<R>>,
+    request: Result<Json<SubmitJob>, JsonRejection>,
+) -> Result<(StatusCode, Json<JobView>), ApiError>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    let Json(request) = request?;
+    let job = request.into_job(Uuid::new_v4(), state.clock.now(), state.idempotency_ttl)?;
+
+    match state.repository.insert_job(&job).await? {
+        Insertion::Created => Ok((StatusCode::CREATED, Json(job.into()))),
+        Insertion::Existing(existing) | Insertion::Debounced(existing) => {
+            Ok((StatusCode::OK, Json((*existing).into())))
+        }
+    }
+}
+
+pub async fn get_job<R>(
+    State(state): State<ApiState<R>>,
+    job_id: Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<JobView>, ApiError>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    let Path(job_id) = job_id?;
+    let job = state.repository.fetch_job(job_id).await?;
+
+    Ok(Json(job.into()))
+}
+
+pub async fn list_jobs<R>(
+    State(state): State<ApiState<R>>,
+    query: Result<Query<ListJobs>, QueryRejection>,
+) -> Result<Json<JobList>, ApiError>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    let Query(query) = query?;
+    let jobs = state.repository.list_jobs(&query.into_filter()?).await?;
+
+    Ok(Json(JobList {
+        jobs: jobs.into_iter().map(JobView::from).collect(),
+    }))
+}
+
+/// Cancels the job wherever it is; a running attempt is signalled to stop.
+pub async fn cancel_job<R>(
+    State(state): State<ApiState<R>>,
+    job_id: Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<JobView>, ApiError>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    let Path(job_id) = job_id?;
+    state.executor.cancel(job_id).await?;
+    let job = state.repository.fetch_job(job_id).await?;
+
+    Ok(Json(job.into()))
+}
+
+/// Queues a failed job again, due now and with its full `max_attempts`.
+pub async fn requeue_job<R>(
+    State(state): State<ApiState<R>>,
+    job_id: Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<JobView>, ApiError>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    let Path(job_id) = job_id?;
+    let job = state.repository.fetch_job(job_id).await?;
+
+    if job.state != JobState::Failed {
+        return Err(ApiError::Conflict(format!(
+            "job {job_id} is {:?}; only failed jobs can be requeued",
+            job.state
+        )));
+    }
+
+    state
+        .repository
+        .requeue_job(job_id, job.version, state.clock.now())
+        .await?;
+    let job = state.repository.fetch_job(job_id).await?;
+
+    Ok(Json(job.into()))
+}
+
+pub async fn read_logs<R>(
+    State(state): State<ApiState<R>>,
+    job_id: Result<Path<Uuid>, PathRejection>,
+    query: Result<Query<ReadLogs>, QueryRejection>,
+) -> Result<Json<LogPage>, ApiError>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    let Path(job_id) = job_id?;
+    let Query(query) = query?;
+    let (limit, offset) = page(query.limit, query.offset)?;
+
+    // An unknown job is a 404, not an empty log.
+    state.repository.fetch_job(job_id).await?;
+
+    if query.follow {
+        return follow_logs(&state, job_id, offset, limit).await.map(Json);
+    }
+
+    let lines = state.repository.fetch_job_logs(job_id, offset, limit).await?;
+
+    Ok(Json(LogPage {
+        next_offset: offset + lines.len() as i64,
+        lines,
+        finished: false,
+    }))
+}
+
+/// Long-polls for lines past `offset`.
+///
+/// Rules:
+/// 1. Returns as soon as there are lines, up to `limit` of them.
+/// 2. Returns an empty, finished page once the job is in a final state and
+///    its log has been read to the end.
+/// 3. Returns an empty page after `FOLLOW_TIMEOUT`; the client asks again.
+async fn follow_logs<R>(
+    state: &ApiState<R>,
+    job_id: Uuid,
+    offset: i64,
+    limit: i64,
+) -> Result<LogPage, ApiError>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    let mut follower = LogFollower::new(
+        Arc::clone(&state.repository),
+        job_id,
+        offset,
+        state.log_poll_interval,
+    )
+    .with_page_size(limit);
+
+    let next = match tokio::time::timeout(FOLLOW_TIMEOUT, follower.next()).await {
+        Ok(next) => next?,
+        Err(_) => Some(Vec::new()),
+    };
+
+    Ok(LogPage {
+        next_offset: follower.offset(),
+        finished: next.is_none(),
+        lines: next.unwrap_or_default(),
+    })
+}
@@ -1 +1,9 @@
+pub mod error;
+pub mod jobs;
+pub mod server;
 
+pub use error::ApiError;
+pub use server::{router, serve, ApiState};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::routing::{get, post};
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::api::jobs;
+use crate::domain::clock::Clock;
+use crate::executor::Executor;
+use crate::storage::repository::{JobLogRepository, JobRepository};
+
+/// What the handlers share. Cancellation goes through the executor so that
+/// a running attempt is stopped, not just marked.
+pub struct ApiState<R>
+where
+    R: JobRepository + Send + Sync + 'static,
+{
+    pub repository: Arc<R>,
+    pub executor: Arc<Executor<R>>,
+    pub clock: Arc<dyn Clock>,
+    /// How long an idempotency key keeps returning the job it created.
+    pub idempotency_ttl: Duration,
+    /// How often a followed log is polled; at least the log writer's
+    /// flush interval.
+    pub log_poll_interval: Duration,
+}
+
+impl<R> Clone for ApiState<R>
+where
+    R: JobRepository + Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            repository: Arc::clone(&self.repository),
+            executor: Arc::clone(&self.executor),
+            clock: Arc::clone(&self.clock),
+            idempotency_ttl: self.idempotency_ttl,
+            log_poll_interval: self.log_poll_interval,
+        }
+    }
+}
+
+pub fn router<R>(state: ApiState<R>) -> Router
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/jobs", post(jobs::submit_job::<R>).get(jobs::list_jobs::<R>))
+        .route("/jobs/:id", get(jobs::get_job::<R>))
+        .route("/jobs/:id/cancel", post(jobs::cancel_job::<R>))
+        .route("/jobs/:id/requeue", post(jobs::requeue_job::<R>))
+        .route("/jobs/:id/logs", get(jobs::read_logs::<R>))
+        .with_state(state)
+}
+
+/// Serves the API on `listener` until `shutdown` fires and in-flight
+/// requests have finished.
+pub async fn serve<R>(
+    listener: TcpListener,
+    state: ApiState<R>,
+    shutdown: CancellationToken,
+) -> std::io::Result<()>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+}
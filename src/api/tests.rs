@@ -0,0 +1,306 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::api::jobs::{self, ListJobs, LogPage, ReadLogs, SubmitJob, DEFAULT_PAGE_SIZE};
+use crate::api::{ApiError, ApiState};
+use crate::dependencies::DependencyFailurePolicy;
+use crate::domain::failure::Failure;
+use crate::domain::clock::SystemClock;
+use crate::domain::job::{Debounce, Job, DEFAULT_JOB_TYPE, DEFAULT_QUEUE};
+use crate::domain::state::JobState;
+use crate::executor::{Executor, HandlerRegistry};
+use crate::logs::{JobLogEntry, LogLevel};
+use crate::retry::{Backoff, RetryPolicy};
+use crate::storage::memory::MemoryJobs;
+use crate::storage::repository::{JobLogRepository, JobRepository, RepositoryError};
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn submit(body: serde_json::Value) -> Result<Job, ApiError> {
+    let request: SubmitJob = serde_json::from_value(body).unwrap();
+    request.into_job(Uuid::new_v4(), Utc::now(), TTL)
+}
+
+fn field(err: ApiError) -> Option<&'static str> {
+    match err {
+        ApiError::InvalidRequest { field, .. } => field,
+        other => panic!("expected invalid request, got {other:?}"),
+    }
+}
+
+#[test]
+fn minimal_submission_gets_defaults() {
+    let now = Utc::now();
+    let request: SubmitJob = serde_json::from_value(serde_json::json!({ "payload": { "n": 1 } })).unwrap();
+    let job = request.into_job(Uuid::new_v4(), now, TTL).unwrap();
+
+    assert_eq!(job.job_type, DEFAULT_JOB_TYPE);
+    assert_eq!(job.queue, DEFAULT_QUEUE);
+    assert_eq!(job.state, JobState::Queued);
+    assert_eq!(job.run_at, now);
+    assert_eq!(job.max_attempts, 3);
+    assert_eq!(job.timeout, None);
+    assert_eq!(job.tenant_id, None);
+    assert_eq!(job.idempotency_key, None);
+    assert_eq!(job.unique_key, None);
+}
+
+#[test]
+fn unique_key_keeps_existing_job_by_default() {
+    let job = submit(serde_json::json!({ "payload": {}, "unique_key": "reindex:acme" })).unwrap();
+    assert_eq!(job.unique_key.as_deref(), Some("reindex:acme"));
+    assert_eq!(job.debounce, Debounce::Keep);
+
+    let job = submit(serde_json::json!({
+        "payload": {},
+        "unique_key": "reindex:acme",
+        "debounce": "replace_payload",
+    }))
+    .unwrap();
+    assert_eq!(job.debounce, Debounce::ReplacePayload);
+}
+
+#[test]
+fn idempotency_key_expires_after_the_window() {
+    let now = Utc::now();
+    let request: SubmitJob = serde_json::from_value(serde_json::json!({
+        "tenant_id": "acme",
+        "payload": {},
+        "idempotency_key": "order-42",
+    }))
+    .unwrap();
+    let job = request.into_job(Uuid::new_v4(), now, TTL).unwrap();
+
+    assert_eq!(job.tenant_id.as_deref(), Some("acme"));
+    assert_eq!(job.idempotency_key.as_deref(), Some("order-42"));
+    assert_eq!(job.idempotency_expires_at, Some(now + chrono::Duration::seconds(60)));
+}
+
+#[test]
+fn submission_fields_are_applied() {
+    let parent = Uuid::new_v4();
+    let job = submit(serde_json::json!({
+        "job_type": "webhook",
+        "queue": "hooks",
+        "payload": {},
+        "priority": 7,
+        "max_attempts": 5,
+        "timeout_ms": 1500,
+        "depends_on": [parent],
+        "dependency_failure_policy": "run_anyway",
+    }))
+    .unwrap();
+
+    assert_eq!(job.job_type, "webhook");
+    assert_eq!(job.queue, "hooks");
+    assert_eq!(job.priority, 7);
+    assert_eq!(job.max_attempts, 5);
+    assert_eq!(job.timeout, Some(Duration::from_millis(1500)));
+    assert_eq!(job.state, JobState::Blocked);
+    assert_eq!(job.depends_on, vec![parent]);
+    assert_eq!(job.dependency_failure_policy, DependencyFailurePolicy::RunAnyway);
+}
+
+#[test]
+fn invalid_submissions_name_the_field() {
+    let parent = Uuid::new_v4();
+
+    let cases = [
+        (serde_json::json!({ "payload": {}, "job_type": " " }), "job_type"),
+        (serde_json::json!({ "payload": {}, "queue": "" }), "queue"),
+        (serde_json::json!({ "payload": {}, "tenant_id": "" }), "tenant_id"),
+        (serde_json::json!({ "payload": {}, "idempotency_key": "" }), "idempotency_key"),
+        (serde_json::json!({ "payload": {}, "idempotency_key": "k".repeat(256) }), "idempotency_key"),
+        (serde_json::json!({ "payload": {}, "unique_key": " " }), "unique_key"),
+        (serde_json::json!({ "payload": {}, "debounce": "extend_run_at" }), "debounce"),
+        (serde_json::json!({ "payload": {}, "max_attempts": 0 }), "max_attempts"),
+        (serde_json::json!({ "payload": {}, "timeout_ms": 0 }), "timeout_ms"),
+        (serde_json::json!({ "payload": {}, "depends_on": [parent, parent] }), "depends_on"),
+    ];
+
+    for (body, expected) in cases {
+        assert_eq!(field(submit(body).unwrap_err()), Some(expected));
+    }
+}
+
+#[test]
+fn list_paging_is_bounded() {
+    let filter = ListJobs::default().into_filter().unwrap();
+    assert_eq!((filter.limit, filter.offset), (DEFAULT_PAGE_SIZE, 0));
+
+    let too_big = ListJobs { limit: Some(10_000), ..Default::default() };
+    assert_eq!(field(too_big.into_filter().unwrap_err()), Some("limit"));
+
+    let negative = ListJobs { offset: Some(-1), ..Default::default() };
+    assert_eq!(field(negative.into_filter().unwrap_err()), Some("offset"));
+}
+
+#[test]
+fn repository_errors_map_to_statuses() {
+    let job_id = Uuid::new_v4();
+
+    let not_found: ApiError = RepositoryError::NotFound(job_id).into();
+    assert_eq!(not_found.status(), StatusCode::NOT_FOUND);
+
+    let stale: ApiError = RepositoryError::StaleState {
+        job_id,
+        expected: JobState::Failed,
+        actual: JobState::Queued,
+    }
+    .into();
+    assert_eq!(stale.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn errors_render_as_stable_json() {
+    let response = ApiError::invalid("limit", "limit must be between 1 and 500").into_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "error": {
+                "code": "invalid_request",
+                "message": "limit must be between 1 and 500",
+                "field": "limit",
+            }
+        })
+    );
+}
+
+fn state(repository: &Arc<MemoryJobs>) -> ApiState<MemoryJobs> {
+    let executor = Executor::new(
+        Arc::clone(repository),
+        HandlerRegistry::new(),
+        Duration::from_secs(60),
+        RetryPolicy::new(Backoff::Fixed { delay: Duration::from_secs(1) }),
+        Arc::new(SystemClock),
+        "worker-a".to_string(),
+        Duration::from_secs(60),
+    );
+
+    ApiState {
+        repository: Arc::clone(repository),
+        executor: Arc::new(executor),
+        clock: Arc::new(SystemClock),
+        idempotency_ttl: TTL,
+        log_poll_interval: Duration::from_millis(10),
+    }
+}
+
+fn log_entry(job: &Job, message: &str) -> JobLogEntry {
+    JobLogEntry {
+        job_id: job.id,
+        attempt: 1,
+        level: LogLevel::Info,
+        message: message.to_string(),
+        logged_at: Utc::now(),
+    }
+}
+
+async fn follow(state: ApiState<MemoryJobs>, job: &Job, offset: i64) -> LogPage {
+    let query = ReadLogs { offset: Some(offset), follow: true, ..Default::default() };
+    jobs::read_logs(State(state), Ok(Path(job.id)), Ok(Query(query))).await.unwrap().0
+}
+
+#[tokio::test]
+async fn followed_log_delivers_lines_written_after_the_call() {
+    let repository = Arc::new(MemoryJobs::default());
+    let job = Job::new(Uuid::new_v4(), serde_json::json!({}), Utc::now());
+    repository.insert_job(&job).await.unwrap();
+
+    let reader = tokio::spawn({
+        let (state, job) = (state(&repository), job.clone());
+        async move { follow(state, &job, 0).await }
+    });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!reader.is_finished());
+    repository
+        .append_job_logs(&[log_entry(&job, "starting"), log_entry(&job, "halfway")])
+        .await
+        .unwrap();
+
+    let page = tokio::time::timeout(Duration::from_secs(1), reader).await.unwrap().unwrap();
+    let messages: Vec<_> = page.lines.iter().map(|line| line.message.as_str()).collect();
+
+    assert_eq!(messages, ["starting", "halfway"]);
+    assert_eq!(page.next_offset, 2);
+    assert!(!page.finished);
+}
+
+#[tokio::test]
+async fn followed_log_ends_once_the_job_has_finished() {
+    let repository = Arc::new(MemoryJobs::default());
+    let job = Job {
+        state: JobState::Succeeded,
+        ..Job::new(Uuid::new_v4(), serde_json::json!({}), Utc::now())
+    };
+    repository.insert_job(&job).await.unwrap();
+    repository.append_job_logs(&[log_entry(&job, "done")]).await.unwrap();
+
+    let page = follow(state(&repository), &job, 0).await;
+    assert_eq!(page.lines.len(), 1);
+    assert!(!page.finished);
+
+    let page = follow(state(&repository), &job, page.next_offset).await;
+    assert!(page.lines.is_empty());
+    assert_eq!(page.next_offset, 1);
+    assert!(page.finished);
+}
+
+#[tokio::test]
+async fn unknown_dependency_is_rejected() {
+    let repository = Arc::new(MemoryJobs::default());
+    let request: SubmitJob = serde_json::from_value(serde_json::json!({
+        "payload": {},
+        "depends_on": [Uuid::new_v4()],
+    }))
+    .unwrap();
+
+    let err = jobs::submit_job(State(state(&repository)), Ok(Json(request))).await.unwrap_err();
+
+    assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(field(err), Some("depends_on"));
+    assert!(repository.list_jobs(&ListJobs::default().into_filter().unwrap()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn requeue_resets_the_attempt_budget() {
+    let repository = Arc::new(MemoryJobs::default());
+    let exhausted = Job {
+        state: JobState::Failed,
+        attempt: 3,
+        max_attempts: 3,
+        run_at: Utc::now() - chrono::Duration::hours(1),
+        failure: Some(Failure::system("connection reset")),
+        ..Job::new(Uuid::new_v4(), serde_json::json!({}), Utc::now())
+    };
+    repository.insert_job(&exhausted).await.unwrap();
+
+    let before = Utc::now();
+    let Json(view) = jobs::requeue_job(State(state(&repository)), Ok(Path(exhausted.id)))
+        .await
+        .unwrap();
+
+    assert_eq!(view.state, JobState::Queued);
+    let stored = repository.job(exhausted.id);
+    assert_eq!((stored.attempt, stored.max_attempts), (0, 3));
+    assert!(stored.run_at >= before);
+    assert!(stored.failure.is_none());
+
+    let err = jobs::requeue_job(State(state(&repository)), Ok(Path(exhausted.id)))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), StatusCode::CONFLICT);
+}
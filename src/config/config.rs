@@ -1,11 +1,36 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::time::Duration;
 
+use crate::domain::failure::FailureKind;
+use crate::recovery::lease_owner;
+use crate::retry::{Backoff, FailureClassification, Retryability};
+use crate::scheduler::{FairShare, SchedulingPolicy};
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
     pub max_concurrency: usize,
+    pub queue_limits: HashMap<String, usize>,
+    pub scheduling_policy: SchedulingPolicy,
     pub scheduler_tick_interval: Duration,
     pub job_timeout: Duration,
+    pub retry_backoff: Backoff,
+    pub failure_classification: FailureClassification,
+    pub misfire_grace: Duration,
+    /// `WORKER_ID`, if set: this worker's identity across restarts.
+    pub instance_id: Option<String>,
+    /// The instance id (or `worker`) plus a per-boot suffix; unique per
+    /// process.
+    pub worker_id: String,
+    pub lease_ttl: Duration,
+    pub cancel_grace: Duration,
+    pub drain_timeout: Duration,
+    pub progress_interval: Duration,
+    pub log_flush_interval: Duration,
+    pub webhook_signing_secret: Option<String>,
+    pub api_bind_addr: SocketAddr,
+    pub idempotency_ttl: Duration,
 }
 
 impl Config {
@@ -18,6 +43,59 @@ impl Config {
             .and_then(|v| v.parse().ok())
             .unwrap_or(10);
 
+        let queue_limits = match std::env::var("QUEUE_CONCURRENCY") {
+            Ok(limits) => limits
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| {
+                    let limit = entry
+                        .split_once('=')
+                        .and_then(|(queue, limit)| Some((queue.trim(), limit.trim().parse().ok()?)))
+                        .filter(|(queue, _)| !queue.is_empty());
+
+                    match limit {
+                        Some((queue, limit)) => (queue.to_string(), limit),
+                        None => panic!(
+                            "QUEUE_CONCURRENCY entries must look like queue=limit (got {entry})"
+                        ),
+                    }
+                })
+                .collect(),
+            Err(_) => HashMap::new(),
+        };
+
+        let tenant_weights = match std::env::var("TENANT_WEIGHTS") {
+            Ok(weights) => weights
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .fold(FairShare::default(), |shares, entry| {
+                    let weight = entry
+                        .split_once('=')
+                        .and_then(|(tenant, weight)| {
+                            Some((tenant.trim(), weight.trim().parse::<u32>().ok()?))
+                        })
+                        .filter(|(_, weight)| *weight > 0);
+
+                    match weight {
+                        Some((tenant, weight)) => shares.with_weight(tenant, weight),
+                        None => panic!(
+                            "TENANT_WEIGHTS entries must look like tenant=weight with weight > 0 (got {entry})"
+                        ),
+                    }
+                }),
+            Err(_) => FairShare::default(),
+        };
+
+        let scheduling_policy = match std::env::var("SCHEDULING_POLICY").as_deref() {
+            Ok("priority") | Err(_) => SchedulingPolicy::Priority,
+            Ok("fair_share") => SchedulingPolicy::FairShare(tenant_weights),
+            Ok(other) => panic!(
+                "SCHEDULING_POLICY must be one of priority, fair_share (got {other})"
+            ),
+        };
+
         let scheduler_tick_interval = std::env::var("SCHEDULER_TICK_MS")
             .ok()
             .and_then(|v| v.parse::<u64>().ok())
@@ -30,11 +108,140 @@ impl Config {
             .map(Duration::from_secs)
             .unwrap_or(Duration::from_secs(5));
 
+        let retry_base_delay = std::env::var("RETRY_BASE_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(1));
+
+        let retry_max_delay = std::env::var("RETRY_MAX_DELAY_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(300));
+
+        let retry_backoff = match std::env::var("RETRY_BACKOFF").as_deref() {
+            Ok("fixed") => Backoff::Fixed { delay: retry_base_delay },
+            Ok("exponential") => Backoff::Exponential {
+                base: retry_base_delay,
+                max: retry_max_delay,
+            },
+            Ok("exponential_jitter") | Err(_) => Backoff::ExponentialJitter {
+                base: retry_base_delay,
+                max: retry_max_delay,
+            },
+            Ok(other) => panic!(
+                "RETRY_BACKOFF must be one of fixed, exponential, exponential_jitter (got {other})"
+            ),
+        };
+
+        let failure_classification = match std::env::var("TERMINAL_FAILURE_KINDS") {
+            Ok(kinds) => kinds
+                .split(',')
+                .map(str::trim)
+                .filter(|kind| !kind.is_empty())
+                .fold(FailureClassification::retry_all(), |table, kind| {
+                    let kind = match kind {
+                        "user_error" => FailureKind::UserError,
+                        "system_error" => FailureKind::SystemError,
+                        "timeout" => FailureKind::Timeout,
+                        other => panic!(
+                            "TERMINAL_FAILURE_KINDS entries must be user_error, system_error or timeout (got {other})"
+                        ),
+                    };
+                    table.with(kind, Retryability::Terminal)
+                }),
+            Err(_) => FailureClassification::default(),
+        };
+
+        let misfire_grace = std::env::var("MISFIRE_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(60));
+
+        let instance_id = std::env::var("WORKER_ID").ok().filter(|v| !v.is_empty());
+
+        // Leases are held per process: the boot id tells a restarted
+        // instance's leases from those of the boot that crashed.
+        let worker_id = lease_owner(
+            instance_id.as_deref().unwrap_or("worker"),
+            uuid::Uuid::new_v4(),
+        );
+
+        let lease_ttl = match std::env::var("LEASE_TTL_SECS") {
+            Ok(secs) => Duration::from_secs(positive_integer("LEASE_TTL_SECS", &secs)),
+            Err(_) => Duration::from_secs(30),
+        };
+
+        let cancel_grace = std::env::var("CANCEL_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(10));
+
+        let drain_timeout = std::env::var("DRAIN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(30));
+
+        let progress_interval = match std::env::var("PROGRESS_INTERVAL_MS") {
+            Ok(ms) => Duration::from_millis(positive_integer("PROGRESS_INTERVAL_MS", &ms)),
+            Err(_) => Duration::from_secs(1),
+        };
+
+        let log_flush_interval = match std::env::var("LOG_FLUSH_INTERVAL_MS") {
+            Ok(ms) => Duration::from_millis(positive_integer("LOG_FLUSH_INTERVAL_MS", &ms)),
+            Err(_) => Duration::from_millis(500),
+        };
+
+        let webhook_signing_secret = std::env::var("WEBHOOK_SIGNING_SECRET")
+            .ok()
+            .filter(|v| !v.is_empty());
+
+        let api_bind_addr = std::env::var("API_BIND_ADDR")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(SocketAddr::from(([127, 0, 0, 1], 8080)));
+
+        let idempotency_ttl = std::env::var("IDEMPOTENCY_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(24 * 60 * 60));
+
         Self {
             database_url,
             max_concurrency,
+            queue_limits,
+            scheduling_policy,
             scheduler_tick_interval,
             job_timeout,
+            retry_backoff,
+            failure_classification,
+            misfire_grace,
+            instance_id,
+            worker_id,
+            lease_ttl,
+            cancel_grace,
+            drain_timeout,
+            progress_interval,
+            log_flush_interval,
+            webhook_signing_secret,
+            api_bind_addr,
+            idempotency_ttl,
         }
     }
 }
+
+/// Parses a setting that must be a positive integer; anything else is a
+/// startup error rather than a silent fallback to the default.
+fn positive_integer(name: &str, value: &str) -> u64 {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|value| *value > 0)
+        .unwrap_or_else(|| panic!("{name} must be a positive integer (got {value})"))
+}
@@ -0,0 +1,6 @@
+pub mod resolve;
+
+pub use resolve::{resolve_dependencies, BlockedJob, DependencyFailurePolicy, Resolution};
+
+#[cfg(test)]
+mod tests;
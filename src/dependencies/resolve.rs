@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::failure::Failure;
+use crate::domain::job::Job;
+use crate::domain::state::JobState;
+
+/// What a blocked job does when a parent ends without succeeding.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyFailurePolicy {
+    Cancel,
+    Fail,
+    RunAnyway,
+}
+
+/// A blocked job together with the current states of its parents.
+#[derive(Debug, Clone)]
+pub struct BlockedJob {
+    pub job: Job,
+    pub parent_states: Vec<JobState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Resolution {
+    /// At least one parent is still pending.
+    Wait,
+    /// Blocked -> Queued.
+    Promote,
+    /// Blocked -> Cancelled.
+    Cancel,
+    /// Blocked -> Failed.
+    Fail(Failure),
+}
+
+/// Pure dependency resolution for a single blocked job.
+///
+/// Rules:
+/// 1. Succeeded, Failed and Cancelled are final parent states; a Failed
+///    parent that is retried is already back in Queued.
+/// 2. If every parent succeeded, promote.
+/// 3. If a parent ended unsuccessfully, Cancel and Fail act immediately;
+///    RunAnyway waits until every parent is final, then promotes.
+/// 4. Otherwise wait.
+pub fn resolve_dependencies(
+    policy: DependencyFailurePolicy,
+    parent_states: &[JobState],
+) -> Resolution {
+    let unsuccessful = parent_states
+        .iter()
+        .any(|state| matches!(state, JobState::Failed | JobState::Cancelled));
+
+    let all_final = parent_states.iter().all(|state| {
+        matches!(
+            state,
+            JobState::Succeeded | JobState::Failed | JobState::Cancelled
+        )
+    });
+
+    if !unsuccessful {
+        return if all_final {
+            Resolution::Promote
+        } else {
+            Resolution::Wait
+        };
+    }
+
+    match policy {
+        DependencyFailurePolicy::Cancel => Resolution::Cancel,
+        DependencyFailurePolicy::Fail => Resolution::Fail(
+            Failure::system("upstream dependency did not succeed").non_retryable(),
+        ),
+        DependencyFailurePolicy::RunAnyway if all_final => Resolution::Promote,
+        DependencyFailurePolicy::RunAnyway => Resolution::Wait,
+    }
+}
@@ -0,0 +1,49 @@
+use crate::dependencies::{resolve_dependencies, DependencyFailurePolicy, Resolution};
+use crate::domain::state::JobState;
+
+use DependencyFailurePolicy::*;
+use JobState::*;
+
+#[test]
+fn promotes_once_all_parents_succeed() {
+    assert!(matches!(
+        resolve_dependencies(Cancel, &[Succeeded, Running]),
+        Resolution::Wait
+    ));
+
+    assert!(matches!(
+        resolve_dependencies(Cancel, &[Succeeded, Succeeded]),
+        Resolution::Promote
+    ));
+}
+
+#[test]
+fn unsuccessful_parent_follows_policy() {
+    assert!(matches!(
+        resolve_dependencies(Cancel, &[Failed, Running]),
+        Resolution::Cancel
+    ));
+
+    assert!(matches!(
+        resolve_dependencies(Fail, &[Cancelled, Queued]),
+        Resolution::Fail(_)
+    ));
+
+    assert!(matches!(
+        resolve_dependencies(RunAnyway, &[Failed, Running]),
+        Resolution::Wait
+    ));
+
+    assert!(matches!(
+        resolve_dependencies(RunAnyway, &[Failed, Succeeded]),
+        Resolution::Promote
+    ));
+}
+
+#[test]
+fn blocked_parent_keeps_dependent_waiting() {
+    assert!(matches!(
+        resolve_dependencies(Cancel, &[Blocked, Succeeded]),
+        Resolution::Wait
+    ));
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+
+/// Source of the current time.
+///
+/// Everything that compares against "now" (due checks, retry delays) takes
+/// its time from an injected clock so that decisions can be replayed.
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall-clock time.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
@@ -1,10 +1,15 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone)]
 pub struct Failure {
     pub kind: FailureKind,
     pub reason: String,
+    /// Handler override of the kind-based retry classification.
+    pub retryable: Option<bool>,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FailureKind {
     UserError,
     SystemError,
@@ -16,6 +21,7 @@ impl Failure {
         Self {
             kind: FailureKind::UserError,
             reason: reason.into(),
+            retryable: None,
         }
     }
 
@@ -23,6 +29,7 @@ impl Failure {
         Self {
             kind: FailureKind::SystemError,
             reason: reason.into(),
+            retryable: None,
         }
     }
 
@@ -30,6 +37,19 @@ impl Failure {
         Self {
             kind: FailureKind::Timeout,
             reason: reason.into(),
+            retryable: None,
         }
     }
+
+    /// Never retry this failure, whatever its kind.
+    pub fn non_retryable(mut self) -> Self {
+        self.retryable = Some(false);
+        self
+    }
+
+    /// Retry this failure while attempts remain, whatever its kind.
+    pub fn retryable(mut self) -> Self {
+        self.retryable = Some(true);
+        self
+    }
 }
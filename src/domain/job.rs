@@ -1,28 +1,187 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::dependencies::DependencyFailurePolicy;
 use crate::domain::state::JobState;
 use crate::domain::failure::Failure;
+use crate::retry::FailureClassification;
+
+/// Job type of jobs submitted without one.
+pub const DEFAULT_JOB_TYPE: &str = "default";
+
+/// Queue of jobs submitted without one.
+pub const DEFAULT_QUEUE: &str = "default";
+
+/// What submitting a job does when a live job already holds its unique
+/// key.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Debounce {
+    /// Leave the existing job as it is.
+    #[default]
+    Keep,
+    /// Give the existing job, if still pending, the new payload.
+    ReplacePayload,
+    /// Push the existing job's `run_at`, if still pending, out to the new
+    /// job's.
+    ExtendRunAt,
+}
 
 #[derive(Debug, Clone)]
 pub struct Job {
     pub id: Uuid,
+    /// Name of the registered handler that runs the job.
+    pub job_type: String,
+    /// Named queue whose concurrency limit the job counts against.
+    pub queue: String,
+    /// Tenant that submitted the job, if any.
+    pub tenant_id: Option<String>,
     pub payload: serde_json::Value,
     pub priority: i32,
 
     pub state: JobState,
+    /// Bumped by every write; repository mutations compare-and-swap on it.
+    pub version: i64,
 
     pub attempt: u32,
     pub max_attempts: u32,
+    /// Earliest time the job may be selected for execution.
+    pub run_at: DateTime<Utc>,
+    /// Overrides the job type's and the global execution timeout.
+    pub timeout: Option<Duration>,
+    /// Per-job override of the policy-wide failure classification.
+    pub failure_classification: Option<FailureClassification>,
 
     pub failure: Option<Failure>,
+    /// Output of the handler, set when the job succeeds.
+    pub result: Option<serde_json::Value>,
+
+    /// Percent complete, as last reported by the handler.
+    pub progress: Option<u8>,
+    /// Opaque resume state saved by the handler; handed to later attempts.
+    pub checkpoint: Option<Vec<u8>>,
+
+    /// Jobs that must succeed before this one is queued.
+    pub depends_on: Vec<Uuid>,
+    pub dependency_failure_policy: DependencyFailurePolicy,
+
+    /// Worker currently executing the job and until when it is trusted to.
+    pub lease_owner: Option<String>,
+    pub lease_expires_at: Option<DateTime<Utc>>,
+
+    /// Resubmissions with this key in the same tenant return this job
+    /// instead of creating another, until the key expires.
+    pub idempotency_key: Option<String>,
+    pub idempotency_expires_at: Option<DateTime<Utc>>,
+
+    /// At most one blocked, queued or running job per key in a tenant.
+    pub unique_key: Option<String>,
+    /// Applied on insert when a live job holds `unique_key`; not stored.
+    pub debounce: Debounce,
+
+    /// Recurring definition and fire time this job was materialized from.
+    pub recurring_job_id: Option<Uuid>,
+    pub fire_time: Option<DateTime<Utc>>,
 
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 impl Job {
+    /// A new queued job, due immediately, with default priority and attempts.
+    pub fn new(id: Uuid, payload: serde_json::Value, now: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            job_type: DEFAULT_JOB_TYPE.to_string(),
+            queue: DEFAULT_QUEUE.to_string(),
+            tenant_id: None,
+            payload,
+            priority: 0,
+            state: JobState::Queued,
+            version: 0,
+            attempt: 0,
+            max_attempts: 3,
+            run_at: now,
+            timeout: None,
+            failure_classification: None,
+            failure: None,
+            result: None,
+            progress: None,
+            checkpoint: None,
+            depends_on: Vec::new(),
+            dependency_failure_policy: DependencyFailurePolicy::Cancel,
+            lease_owner: None,
+            lease_expires_at: None,
+            idempotency_key: None,
+            idempotency_expires_at: None,
+            unique_key: None,
+            debounce: Debounce::Keep,
+            recurring_job_id: None,
+            fire_time: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn with_job_type(mut self, job_type: impl Into<String>) -> Self {
+        self.job_type = job_type.into();
+        self
+    }
+
+    pub fn with_queue(mut self, queue: impl Into<String>) -> Self {
+        self.queue = queue.into();
+        self
+    }
+
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    pub fn with_idempotency_key(
+        mut self,
+        key: impl Into<String>,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        self.idempotency_key = Some(key.into());
+        self.idempotency_expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn with_unique_key(mut self, key: impl Into<String>, debounce: Debounce) -> Self {
+        self.unique_key = Some(key.into());
+        self.debounce = debounce;
+        self
+    }
+
+    /// Makes the job wait in Blocked until `parents` have finished.
+    pub fn with_dependencies(
+        mut self,
+        parents: Vec<Uuid>,
+        policy: DependencyFailurePolicy,
+    ) -> Self {
+        if !parents.is_empty() {
+            self.state = JobState::Blocked;
+        }
+        self.depends_on = parents;
+        self.dependency_failure_policy = policy;
+        self
+    }
+
     pub fn can_retry(&self) -> bool {
         self.attempt < self.max_attempts
     }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.run_at <= now
+    }
+
+    /// True when no live worker holds the job: the lease is missing or
+    /// ran out at or before `now`.
+    pub fn lease_expired(&self, now: DateTime<Utc>) -> bool {
+        self.lease_expires_at.is_none_or(|expires_at| expires_at <= now)
+    }
 }
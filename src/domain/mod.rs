@@ -1,3 +1,4 @@
 pub mod job;
 pub mod state;
 pub mod failure;
+pub mod clock;
@@ -1,7 +1,12 @@
+use serde::{Deserialize, Serialize};
+
 use crate::domain::failure::Failure;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum JobState {
+    /// Waiting for parent jobs to finish.
+    Blocked,
     Queued,
     Running,
     Succeeded,
@@ -9,8 +14,9 @@ pub enum JobState {
     Cancelled,
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum StateTransitionError {
+    #[error("invalid state transition from {from:?} to {to:?}")]
     InvalidTransition {
         from: JobState,
         to: JobState,
@@ -18,6 +24,11 @@ pub enum StateTransitionError {
 }
 
 impl JobState {
+    /// True for states a job does not leave on its own.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, JobState::Succeeded | JobState::Failed | JobState::Cancelled)
+    }
+
     pub fn transition(
         self,
         next: JobState,
@@ -25,14 +36,20 @@ impl JobState {
     ) -> Result<JobState, StateTransitionError> {
         use JobState::*;
 
-        let valid = match (self, next) {
-            (Queued, Running) => true,
-            (Running, Succeeded) => true,
-            (Running, Failed) => true,
-            (Queued, Cancelled) => true,
-            (Running, Cancelled) => true,
-            _ => false,
-        };
+        let valid = matches!(
+            (self, next),
+            (Blocked, Queued)
+                | (Blocked, Cancelled)
+                | (Blocked, Failed)
+                | (Queued, Running)
+                | (Running, Succeeded)
+                | (Running, Failed)
+                // Released unfinished by a worker shutting down.
+                | (Running, Queued)
+                | (Failed, Queued)
+                | (Queued, Cancelled)
+                | (Running, Cancelled)
+        );
 
         if !valid {
             return Err(StateTransitionError::InvalidTransition {
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::Span;
+use uuid::Uuid;
+
+use crate::domain::clock::Clock;
+use crate::domain::job::Job;
+use crate::logs::{JobLogEntry, LogLevel, LogSink};
+
+/// Everything a handler needs to run one attempt of a job.
+pub struct JobContext {
+    pub job_id: Uuid,
+    pub job_type: String,
+    pub payload: serde_json::Value,
+
+    /// 1-based number of this attempt.
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// Last checkpoint saved by an earlier attempt, to resume from.
+    pub checkpoint: Option<Vec<u8>>,
+
+    /// When the executor gives up on this attempt.
+    pub deadline: Instant,
+    /// Signalled when the executor abandons the attempt; handlers should
+    /// stop promptly once it fires.
+    pub cancellation: CancellationToken,
+
+    pub progress: ProgressReporter,
+    pub logger: JobLogger,
+}
+
+impl JobContext {
+    pub fn new(
+        job: &Job,
+        deadline: Instant,
+        cancellation: CancellationToken,
+        progress: ProgressReporter,
+        logger: JobLogger,
+    ) -> Self {
+        Self {
+            job_id: job.id,
+            job_type: job.job_type.clone(),
+            payload: job.payload.clone(),
+            attempt: job.attempt + 1,
+            max_attempts: job.max_attempts,
+            checkpoint: job.checkpoint.clone(),
+            deadline,
+            cancellation,
+            progress,
+            logger,
+        }
+    }
+
+    /// Time left before the deadline; zero once it has passed.
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Latest progress reported by a handler.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub percent: Option<u8>,
+    pub checkpoint: Option<Vec<u8>>,
+}
+
+/// Reports progress and checkpoints to the executor, which saves them on
+/// the job at a throttled rate. Only the latest values are kept, so
+/// reporting often is cheap.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    sender: Arc<watch::Sender<Progress>>,
+}
+
+impl ProgressReporter {
+    /// A reporter and the receiving end the executor watches.
+    pub fn channel() -> (Self, watch::Receiver<Progress>) {
+        let (sender, receiver) = watch::channel(Progress::default());
+        (Self { sender: Arc::new(sender) }, receiver)
+    }
+
+    /// Reports `percent` complete; values above 100 are clamped.
+    pub fn report(&self, percent: u8) {
+        self.sender.send_modify(|progress| progress.percent = Some(percent.min(100)));
+    }
+
+    /// Saves resume state; a retry of the job receives the latest one in
+    /// `JobContext::checkpoint`.
+    pub fn checkpoint(&self, data: impl Into<Vec<u8>>) {
+        let data = data.into();
+        self.sender.send_modify(|progress| progress.checkpoint = Some(data));
+    }
+}
+
+/// Logger whose lines carry the job id, job type and attempt.
+///
+/// Lines go to the process's tracing output and, with a sink, to the job's
+/// stored log, stamped with the clock's time.
+#[derive(Clone)]
+pub struct JobLogger {
+    span: Span,
+    job_id: Uuid,
+    attempt: u32,
+    sink: Option<LogSink>,
+    clock: Arc<dyn Clock>,
+}
+
+impl JobLogger {
+    pub fn new(job: &Job, sink: Option<LogSink>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            span: tracing::info_span!(
+                "job",
+                job_id = %job.id,
+                job_type = %job.job_type,
+                attempt = job.attempt + 1,
+            ),
+            job_id: job.id,
+            attempt: job.attempt + 1,
+            sink,
+            clock,
+        }
+    }
+
+    /// Span the attempt runs in; anything traced inside it is attributed
+    /// to the job.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn info(&self, message: &str) {
+        self.span.in_scope(|| tracing::info!("{message}"));
+        self.store(LogLevel::Info, message);
+    }
+
+    pub fn warn(&self, message: &str) {
+        self.span.in_scope(|| tracing::warn!("{message}"));
+        self.store(LogLevel::Warn, message);
+    }
+
+    pub fn error(&self, message: &str) {
+        self.span.in_scope(|| tracing::error!("{message}"));
+        self.store(LogLevel::Error, message);
+    }
+
+    fn store(&self, level: LogLevel, message: &str) {
+        if let Some(sink) = &self.sink {
+            sink.send(JobLogEntry {
+                job_id: self.job_id,
+                attempt: self.attempt,
+                level,
+                message: message.to_string(),
+                logged_at: self.clock.now(),
+            });
+        }
+    }
+}
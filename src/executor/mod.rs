@@ -1,4 +1,14 @@
+pub mod context;
+pub mod registry;
 pub mod runner;
 pub mod sleep_handler;
+#[cfg(unix)]
+pub mod subprocess_handler;
+pub mod webhook_handler;
 
+pub use context::{JobContext, JobLogger, ProgressReporter};
+pub use registry::HandlerRegistry;
 pub use runner::Executor;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::executor::runner::JobHandler;
+
+struct Registration {
+    handler: Arc<dyn JobHandler>,
+    timeout: Option<Duration>,
+}
+
+/// Maps job type names to the handlers that run them.
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: HashMap<String, Registration>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `handler` for `job_type`, replacing any previous handler.
+    pub fn register(self, job_type: impl Into<String>, handler: Arc<dyn JobHandler>) -> Self {
+        self.insert(job_type.into(), Registration { handler, timeout: None })
+    }
+
+    /// Like `register`, with a default timeout for jobs of this type that
+    /// do not set their own.
+    pub fn register_with_timeout(
+        self,
+        job_type: impl Into<String>,
+        handler: Arc<dyn JobHandler>,
+        timeout: Duration,
+    ) -> Self {
+        self.insert(job_type.into(), Registration { handler, timeout: Some(timeout) })
+    }
+
+    fn insert(mut self, job_type: String, registration: Registration) -> Self {
+        self.handlers.insert(job_type, registration);
+        self
+    }
+
+    pub fn get(&self, job_type: &str) -> Option<Arc<dyn JobHandler>> {
+        self.handlers
+            .get(job_type)
+            .map(|registration| Arc::clone(&registration.handler))
+    }
+
+    /// Default timeout registered for `job_type`, if any.
+    pub fn timeout(&self, job_type: &str) -> Option<Duration> {
+        self.handlers.get(job_type)?.timeout
+    }
+
+    /// Registered job types, sorted.
+    pub fn job_types(&self) -> Vec<&str> {
+        let mut job_types: Vec<_> = self.handlers.keys().map(String::as_str).collect();
+        job_types.sort_unstable();
+        job_types
+    }
+}
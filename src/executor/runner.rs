@@ -1,68 +1,509 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use tokio::task::JoinHandle;
-use tokio::time::timeout;
+use tokio::sync::watch;
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio::time::{interval, sleep, timeout, timeout_at, Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, error, info, warn, Instrument};
 use uuid::Uuid;
 
+use crate::domain::clock::Clock;
 use crate::domain::failure::Failure;
+use crate::domain::job::Job;
 use crate::domain::state::JobState;
-use crate::storage::repository::JobRepository;
+use crate::executor::context::{JobContext, JobLogger, Progress, ProgressReporter};
+use crate::executor::registry::HandlerRegistry;
+use crate::logs::LogSink;
+use crate::retry::RetryPolicy;
+use crate::storage::repository::{FailGuard, JobRepository, RepositoryError};
 
 #[async_trait::async_trait]
 pub trait JobHandler: Send + Sync + 'static {
-    async fn execute(&self, job_id: Uuid) -> Result<(), Failure>;
+    /// Runs one attempt. On success the handler may return a JSON result,
+    /// stored on the job.
+    async fn execute(&self, ctx: JobContext) -> Result<Option<serde_json::Value>, Failure>;
 }
 
-pub struct Executor<R, H>
+/// How long a cancelled handler gets to stop on its own before its task is
+/// aborted.
+pub const DEFAULT_CANCEL_GRACE: Duration = Duration::from_secs(10);
+
+/// How often handler progress is saved at most.
+pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Attempts at cancelling a job whose version keeps moving, e.g. because
+/// its lease is being renewed.
+const MAX_CANCEL_ATTEMPTS: usize = 3;
+
+/// A job currently executing in this process.
+struct RunningTask {
+    cancellation: CancellationToken,
+    /// Asks the task to stop and hand its job back to the queue.
+    release: CancellationToken,
+    abort: AbortHandle,
+}
+
+type RunningTasks = Arc<Mutex<HashMap<Uuid, RunningTask>>>;
+
+/// Removes a task from the running set however its future ends, including
+/// when it is aborted.
+struct Deregister {
+    tasks: RunningTasks,
+    job_id: Uuid,
+}
+
+impl Drop for Deregister {
+    fn drop(&mut self) {
+        self.tasks.lock().unwrap().remove(&self.job_id);
+    }
+}
+
+pub struct Executor<R>
 where
     R: JobRepository + Send + Sync + 'static,
-    H: JobHandler + Send + Sync + 'static,
 {
     repository: Arc<R>,
-    handler: Arc<H>,
+    handlers: Arc<HandlerRegistry>,
     job_timeout: Duration,
+    retry_policy: Arc<RetryPolicy>,
+    clock: Arc<dyn Clock>,
+    worker_id: Arc<str>,
+    lease_ttl: Duration,
+    cancel_grace: Duration,
+    progress_interval: Duration,
+    log_sink: Option<LogSink>,
+    running: RunningTasks,
+    tracker: TaskTracker,
 }
 
-impl<R, H> Executor<R, H>
+impl<R> Executor<R>
 where
     R: JobRepository + Send + Sync + 'static,
-    H: JobHandler + Send + Sync + 'static,
 {
     pub fn new(
         repository: Arc<R>,
-        handler: Arc<H>,
+        handlers: HandlerRegistry,
         job_timeout: Duration,
+        retry_policy: RetryPolicy,
+        clock: Arc<dyn Clock>,
+        worker_id: String,
+        lease_ttl: Duration,
     ) -> Self {
-        Self { repository, handler, job_timeout }
+        Self {
+            repository,
+            handlers: Arc::new(handlers),
+            job_timeout,
+            retry_policy: Arc::new(retry_policy),
+            clock,
+            worker_id: worker_id.into(),
+            lease_ttl,
+            cancel_grace: DEFAULT_CANCEL_GRACE,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+            log_sink: None,
+            running: Arc::default(),
+            tracker: TaskTracker::new(),
+        }
+    }
+
+    pub fn with_cancel_grace(mut self, cancel_grace: Duration) -> Self {
+        self.cancel_grace = cancel_grace;
+        self
+    }
+
+    pub fn with_progress_interval(mut self, progress_interval: Duration) -> Self {
+        self.progress_interval = progress_interval;
+        self
+    }
+
+    /// Stores handler log lines through `log_sink` in addition to tracing.
+    pub fn with_log_sink(mut self, log_sink: LogSink) -> Self {
+        self.log_sink = Some(log_sink);
+        self
+    }
+
+    /// Identity under which jobs run by this executor are leased.
+    pub fn worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    pub fn lease_ttl(&self) -> Duration {
+        self.lease_ttl
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
     }
 
-    pub fn spawn(&self, job_id: Uuid) -> JoinHandle<()> {
+    /// Runs a job claimed by this worker with the handler registered for
+    /// its job type; jobs of an unknown type fail with a user error.
+    ///
+    /// While the handler runs, the lease is renewed every third of its TTL.
+    /// Writes for the job are guarded by the lease, not the version, so
+    /// edits made meanwhile do not disturb it. If the lease is lost (the
+    /// job was reaped or cancelled), the handler is dropped and no outcome
+    /// is recorded. A job released by `shutdown` is likewise dropped and
+    /// handed back to the queue.
+    pub fn spawn(&self, job: Job) -> JoinHandle<()> {
+        let job_id = job.id;
+        let cancellation = CancellationToken::new();
+        let mut running = self.running.lock().unwrap();
+
+        let deregister = Deregister {
+            tasks: Arc::clone(&self.running),
+            job_id,
+        };
+        let task_cancellation = cancellation.clone();
+        let release = CancellationToken::new();
+        let task_release = release.clone();
+
         let repo = Arc::clone(&self.repository);
-        let handler = Arc::clone(&self.handler);
-        let timeout_duration = self.job_timeout;
+        let handlers = Arc::clone(&self.handlers);
+        let retry_policy = Arc::clone(&self.retry_policy);
+        let clock = Arc::clone(&self.clock);
+        let worker_id = Arc::clone(&self.worker_id);
+        let lease_ttl = self.lease_ttl;
+        let global_timeout = self.job_timeout;
+        let progress_interval = self.progress_interval;
+        let log_sink = self.log_sink.clone();
+
+        let handle = self.tracker.spawn(async move {
+            let _deregister = deregister;
+            let cancellation = task_cancellation;
+            let release = task_release;
+
+            let Some(handler) = handlers.get(&job.job_type) else {
+                let failure = Failure::user(format!(
+                    "no handler registered for job type '{}'",
+                    job.job_type
+                ));
+                fail(repo.as_ref(), &retry_policy, clock.as_ref(), &worker_id, job, failure).await;
+                return;
+            };
+
+            let (timeout_duration, timeout_source) = effective_timeout(
+                job.timeout,
+                handlers.timeout(&job.job_type),
+                global_timeout,
+            );
+
+            // Whichever way this task ends, work the handler left behind is
+            // told to stop.
+            let _cancel_on_exit = cancellation.clone().drop_guard();
+
+            let (progress, mut progress_updates) = ProgressReporter::channel();
+            let ctx = JobContext::new(
+                &job,
+                Instant::now() + timeout_duration,
+                cancellation.clone(),
+                progress,
+                JobLogger::new(&job, log_sink, Arc::clone(&clock)),
+            );
+            let span = ctx.logger.span().clone();
+
+            let mut execution = Box::pin(timeout_at(
+                ctx.deadline,
+                handler.execute(ctx).instrument(span),
+            ));
+
+            let mut heartbeat = interval(lease_ttl / 3);
+            heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            heartbeat.tick().await;
+
+            // Progress is saved at most once per interval, whatever the
+            // handler's reporting rate.
+            let mut progress_flush = interval(progress_interval);
+            progress_flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            progress_flush.tick().await;
+            let mut saved_progress = Progress {
+                percent: job.progress,
+                checkpoint: job.checkpoint.clone(),
+            };
+
+            let result = loop {
+                tokio::select! {
+                    result = &mut execution => break Some(result),
+                    _ = release.cancelled() => break None,
+                    _ = progress_flush.tick() => {
+                        save_progress(
+                            repo.as_ref(), &worker_id, &job, &mut saved_progress, &mut progress_updates
+                        ).await;
+                    }
+                    _ = heartbeat.tick() => {
+                        // A cancelled job is no longer ours to renew; the
+                        // handler gets its grace period to wind down.
+                        if cancellation.is_cancelled() {
+                            continue;
+                        }
 
-        tokio::spawn(async move {
-            let result = timeout(timeout_duration, handler.execute(job_id)).await;
+                        let expires_at = clock.now() + lease_ttl;
+
+                        match repo.renew_lease(job.id, &worker_id, expires_at).await {
+                            Ok(_) => {}
+                            Err(RepositoryError::Database(err)) => warn!(
+                                job_id = %job.id,
+                                error = ?err,
+                                "failed to renew job lease; retrying on next heartbeat"
+                            ),
+                            Err(err) => {
+                                warn!(
+                                    job_id = %job.id,
+                                    error = %err,
+                                    "job lease lost; abandoning execution"
+                                );
+                                return;
+                            }
+                        }
+                    }
+                }
+            };
+
+            // Stop the handler before the job becomes claimable again, and
+            // keep the last checkpoint it reported for the next attempt.
+            drop(execution);
+
+            if !cancellation.is_cancelled() {
+                save_progress(
+                    repo.as_ref(), &worker_id, &job, &mut saved_progress, &mut progress_updates
+                ).await;
+            }
+
+            let Some(result) = result else {
+                if let Err(err) = repo.release_job(job.id, &worker_id, "shutdown").await {
+                    report_lost_outcome(job.id, "release", err);
+                }
+                return;
+            };
 
             match result {
-                Ok(Ok(())) => {
-                    let _ = repo.update_job_state(
-                        job_id, JobState::Running, JobState::Succeeded, None
-                    ).await;
+                Ok(Ok(output)) => {
+                    let recorded = repo.complete_job(job.id, &worker_id, output.as_ref()).await;
+
+                    if let Err(err) = recorded {
+                        report_lost_outcome(job.id, "success", err);
+                    }
                 }
                 Ok(Err(failure)) => {
-                    let _ = repo.update_job_state(
-                        job_id, JobState::Running, JobState::Failed, Some(&failure)
-                    ).await;
+                    fail(repo.as_ref(), &retry_policy, clock.as_ref(), &worker_id, job, failure).await;
                 }
                 Err(_) => {
-                    let failure = Failure::timeout("job execution exceeded timeout");
-                    let _ = repo.update_job_state(
-                        job_id, JobState::Running, JobState::Failed, Some(&failure)
-                    ).await;
+                    let failure = Failure::timeout(format!(
+                        "job execution exceeded {timeout_source} timeout of {timeout_duration:?}"
+                    ));
+                    fail(repo.as_ref(), &retry_policy, clock.as_ref(), &worker_id, job, failure).await;
                 }
             }
+        });
+
+        running.insert(job_id, RunningTask { cancellation, release, abort: handle.abort_handle() });
+        handle
+    }
+
+    /// Waits up to `drain_timeout` for running jobs to finish, then releases
+    /// the ones still running back to Queued with reason `shutdown`.
+    ///
+    /// The caller must have stopped spawning jobs.
+    pub async fn shutdown(&self, drain_timeout: Duration) {
+        self.tracker.close();
+
+        if timeout(drain_timeout, self.tracker.wait()).await.is_ok() {
+            return;
+        }
+
+        let unfinished: Vec<_> = self
+            .running
+            .lock()
+            .unwrap()
+            .values()
+            .map(|task| task.release.clone())
+            .collect();
+
+        warn!(
+            unfinished = unfinished.len(),
+            "drain timeout elapsed; releasing unfinished jobs"
+        );
+
+        for release in unfinished {
+            release.cancel();
+        }
+
+        self.tracker.wait().await;
+    }
+
+    /// Cancels a job that has not finished yet.
+    ///
+    /// Rules:
+    /// 1. The job is marked Cancelled first; outcomes its handler reports
+    ///    afterwards are discarded.
+    /// 2. If the job runs in this process, its cancellation token is
+    ///    signalled and the task is aborted after the cancel grace period.
+    /// 3. A job running on another worker stops at that worker's next
+    ///    heartbeat, which finds the lease gone.
+    ///
+    /// Returns the new version.
+    pub async fn cancel(&self, job_id: Uuid) -> Result<i64, RepositoryError> {
+        let mut attempts = 0;
+
+        let version = loop {
+            attempts += 1;
+            let job = self.repository.fetch_job(job_id).await?;
+
+            let cancelled = self
+                .repository
+                .update_job_state(job_id, job.version, job.state, JobState::Cancelled, None)
+                .await;
+
+            match cancelled {
+                Ok(version) => break version,
+                Err(RepositoryError::VersionConflict { .. } | RepositoryError::StaleState { .. })
+                    if attempts < MAX_CANCEL_ATTEMPTS => continue,
+                Err(err) => return Err(err),
+            }
+        };
+
+        let task = self
+            .running
+            .lock()
+            .unwrap()
+            .get(&job_id)
+            .map(|task| (task.cancellation.clone(), task.abort.clone()));
+
+        if let Some((cancellation, abort)) = task {
+            cancellation.cancel();
+
+            let grace = self.cancel_grace;
+            tokio::spawn(async move {
+                sleep(grace).await;
+
+                if !abort.is_finished() {
+                    warn!(job_id = %job_id, "cancelled job ignored its token; aborting");
+                    abort.abort();
+                }
+            });
+        }
+
+        info!(job_id = %job_id, "job cancelled");
+        Ok(version)
+    }
+}
+
+/// Saves whatever the handler reported since the last save. Failures are
+/// logged; a lost lease is noticed by the next heartbeat.
+async fn save_progress<R>(
+    repo: &R,
+    worker_id: &str,
+    job: &Job,
+    saved: &mut Progress,
+    updates: &mut watch::Receiver<Progress>,
+) where
+    R: JobRepository + Send + Sync + 'static,
+{
+    // Checked through the value rather than the channel, which is closed
+    // once the handler has returned.
+    if !updates.borrow().has_changed() {
+        return;
+    }
+
+    let latest = updates.borrow_and_update().clone();
+    let percent = latest.percent.filter(|percent| saved.percent != Some(*percent));
+    let checkpoint = latest
+        .checkpoint
+        .as_deref()
+        .filter(|checkpoint| saved.checkpoint.as_deref() != Some(*checkpoint));
+
+    if percent.is_none() && checkpoint.is_none() {
+        return;
+    }
+
+    match repo.save_progress(job.id, worker_id, percent, checkpoint).await {
+        Ok(_) => {
+            debug!(job_id = %job.id, progress = ?latest.percent, "saved job progress");
+            *saved = latest;
+        }
+        Err(err) => {
+            warn!(job_id = %job.id, error = %err, "failed to save job progress");
+            // Retry with the next flush.
+            updates.mark_changed();
+        }
+    }
+}
+
+/// Which setting an attempt's timeout came from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TimeoutSource {
+    Job,
+    JobType,
+    Global,
+}
+
+impl std::fmt::Display for TimeoutSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TimeoutSource::Job => "job",
+            TimeoutSource::JobType => "job type",
+            TimeoutSource::Global => "global",
         })
     }
 }
+
+/// Picks the most specific timeout: the job's own, then its type's
+/// default, then the global one.
+pub fn effective_timeout(
+    job: Option<Duration>,
+    job_type: Option<Duration>,
+    global: Duration,
+) -> (Duration, TimeoutSource) {
+    match (job, job_type) {
+        (Some(timeout), _) => (timeout, TimeoutSource::Job),
+        (None, Some(timeout)) => (timeout, TimeoutSource::JobType),
+        (None, None) => (global, TimeoutSource::Global),
+    }
+}
+
+async fn fail<R>(
+    repo: &R,
+    retry_policy: &RetryPolicy,
+    clock: &dyn Clock,
+    worker_id: &str,
+    mut job: Job,
+    failure: Failure,
+) where
+    R: JobRepository + Send + Sync + 'static,
+{
+    // Mirrors the attempt increment persisted by `fail_job`.
+    job.attempt += 1;
+    let decision = retry_policy.decide(&job, &failure, clock.now());
+
+    if let Err(err) = repo.fail_job(job.id, FailGuard::Lease(worker_id), &failure, &decision).await {
+        report_lost_outcome(job.id, "failure", err);
+    }
+}
+
+fn report_lost_outcome(job_id: Uuid, outcome: &str, err: RepositoryError) {
+    match err {
+        RepositoryError::StaleState { actual: JobState::Cancelled, .. } => info!(
+            job_id = %job_id,
+            "job was cancelled; {outcome} discarded"
+        ),
+        RepositoryError::StaleState { actual, .. } => warn!(
+            job_id = %job_id,
+            actual = ?actual,
+            "job left Running before its {outcome} was recorded; outcome discarded"
+        ),
+        RepositoryError::LeaseLost { .. } => warn!(
+            job_id = %job_id,
+            "job lease was lost before its {outcome} was recorded; outcome discarded"
+        ),
+        RepositoryError::NotFound(_) => warn!(
+            job_id = %job_id,
+            "job disappeared before its {outcome} was recorded"
+        ),
+        err => error!(
+            job_id = %job_id,
+            error = ?err,
+            "failed to record job {outcome}"
+        ),
+    }
+}
@@ -1,9 +1,8 @@
 use std::time::Duration;
 
 use tokio::time::sleep;
-use uuid::Uuid;
-
 use crate::domain::failure::Failure;
+use crate::executor::context::JobContext;
 use crate::executor::runner::JobHandler;
 
 /// Simple v1 job handler that simulates work with a sleep.
@@ -11,8 +10,8 @@ pub struct SleepJobHandler;
 
 #[async_trait::async_trait]
 impl JobHandler for SleepJobHandler {
-    async fn execute(&self, _job_id: Uuid) -> Result<(), Failure> {
+    async fn execute(&self, _ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
         sleep(Duration::from_secs(1)).await;
-        Ok(())
+        Ok(None)
     }
 }
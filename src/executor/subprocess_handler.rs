@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+
+use crate::domain::failure::Failure;
+use crate::executor::context::{JobContext, JobLogger};
+use crate::executor::runner::JobHandler;
+
+/// sysexits.h `EX_TEMPFAIL`: the command asks to be tried again later.
+const EX_TEMPFAIL: i32 = 75;
+
+/// How long output is still read once the command has exited, for lines
+/// that something it left running writes to the inherited pipes.
+const OUTPUT_DRAIN: Duration = Duration::from_millis(200);
+
+/// What to run, taken from the job payload.
+#[derive(Debug, Deserialize)]
+pub struct SubprocessSpec {
+    /// Program followed by its arguments; the program is looked up in PATH.
+    pub argv: Vec<String>,
+    /// Added to the worker's own environment.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<PathBuf>,
+    /// Written to the command's stdin, which is then closed.
+    pub stdin: Option<String>,
+}
+
+/// Runs a command described by the job payload in its own process group.
+///
+/// Rules:
+/// 1. stdout lines are logged at info, stderr lines at warn, through the
+///    job's logger; bytes that are not UTF-8 are replaced, not dropped.
+/// 2. Exit code 0 succeeds with `{"exit_code": 0}` as the result.
+/// 3. Exit code 75 (`EX_TEMPFAIL`), failing to start, and death by signal
+///    are system failures; any other exit code is a user failure.
+/// 4. The attempt ends when the command exits, even if something it started
+///    in the background still holds its output open; output is read for
+///    `OUTPUT_DRAIN` more at most.
+/// 5. When the command exits, or the attempt is abandoned (timeout,
+///    cancellation, lease loss, shutdown), the whole process group is
+///    killed.
+pub struct SubprocessJobHandler;
+
+#[async_trait::async_trait]
+impl JobHandler for SubprocessJobHandler {
+    async fn execute(&self, ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        let spec: SubprocessSpec = serde_json::from_value(ctx.payload.clone())
+            .map_err(|err| Failure::user(format!("invalid subprocess payload: {err}")))?;
+
+        let Some((program, args)) = spec.argv.split_first() else {
+            return Err(Failure::user("invalid subprocess payload: argv is empty"));
+        };
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .envs(&spec.env)
+            .process_group(0)
+            .stdin(if spec.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if let Some(cwd) = &spec.cwd {
+            command.current_dir(cwd);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|err| Failure::system(format!("failed to start {program}: {err}")))?;
+
+        let group = ProcessGroup { pgid: child.id().map(|pid| pid as i32) };
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        let run = async {
+            let write_stdin = async {
+                if let (Some(mut pipe), Some(input)) = (stdin, spec.stdin.as_deref()) {
+                    // A command that exits without reading its input is not
+                    // an error of ours.
+                    let _ = pipe.write_all(input.as_bytes()).await;
+                }
+            };
+
+            let exited = CancellationToken::new();
+
+            let wait = async {
+                let status = child.wait().await;
+                exited.cancel();
+                status
+            };
+
+            let output = async {
+                let output = async {
+                    tokio::join!(
+                        write_stdin,
+                        forward_lines(stdout, &ctx.logger, JobLogger::info),
+                        forward_lines(stderr, &ctx.logger, JobLogger::warn),
+                    )
+                };
+                tokio::pin!(output);
+
+                tokio::select! {
+                    _ = &mut output => {}
+                    _ = exited.cancelled() => {
+                        let _ = timeout(OUTPUT_DRAIN, output).await;
+                    }
+                }
+            };
+
+            tokio::join!(wait, output).0
+        };
+
+        let status = tokio::select! {
+            status = run => status
+                .map_err(|err| Failure::system(format!("failed to wait for {program}: {err}")))?,
+            _ = ctx.cancellation.cancelled() => {
+                return Err(Failure::system(format!("{program} stopped: job cancelled")));
+            }
+        };
+
+        // Whatever the command left running goes with it. The group keeps
+        // its id reserved for as long as it has members.
+        drop(group);
+
+        exit_outcome(program, status)
+    }
+}
+
+fn exit_outcome(program: &str, status: ExitStatus) -> Result<Option<serde_json::Value>, Failure> {
+    use std::os::unix::process::ExitStatusExt;
+
+    match (status.code(), status.signal()) {
+        (Some(0), _) => Ok(Some(serde_json::json!({ "exit_code": 0 }))),
+        (Some(EX_TEMPFAIL), _) => Err(Failure::system(format!(
+            "{program} exited with status {EX_TEMPFAIL} (temporary failure)"
+        ))),
+        (Some(code), _) => Err(Failure::user(format!("{program} exited with status {code}"))),
+        (None, Some(signal)) => Err(Failure::system(format!("{program} killed by signal {signal}"))),
+        (None, None) => Err(Failure::system(format!("{program} exited abnormally"))),
+    }
+}
+
+async fn forward_lines<S>(stream: Option<S>, logger: &JobLogger, log: fn(&JobLogger, &str))
+where
+    S: AsyncRead + Unpin,
+{
+    let Some(stream) = stream else {
+        return;
+    };
+
+    let mut stream = BufReader::new(stream);
+    let mut line = Vec::new();
+
+    while let Ok(read) = stream.read_until(b'\n', &mut line).await {
+        if read == 0 {
+            break;
+        }
+
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        log(logger, &String::from_utf8_lossy(text));
+        line.clear();
+    }
+}
+
+/// Kills the command's process group when dropped, so nothing it started
+/// outlives the attempt.
+struct ProcessGroup {
+    pgid: Option<i32>,
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.pgid {
+            // SAFETY: plain syscall; a negative pid addresses the group.
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+    }
+}
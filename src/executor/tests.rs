@@ -0,0 +1,709 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::domain::clock::SystemClock;
+use crate::domain::failure::{Failure, FailureKind};
+use crate::domain::job::{Job, DEFAULT_JOB_TYPE};
+use crate::domain::state::JobState;
+use crate::executor::runner::{effective_timeout, Executor, JobHandler, TimeoutSource};
+use crate::executor::subprocess_handler::SubprocessJobHandler;
+use crate::executor::webhook_handler::{sign, WebhookJobHandler, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::executor::context::Progress;
+use crate::executor::{HandlerRegistry, JobContext, JobLogger, ProgressReporter};
+use crate::logs::LogWriter;
+use crate::retry::{Backoff, RetryPolicy};
+use crate::storage::memory::MemoryJobs;
+use crate::storage::repository::{JobLogRepository, JobRepository, RepositoryError};
+
+const WORKER: &str = "worker-a";
+
+struct FixedHandler(Result<Option<serde_json::Value>, Failure>);
+
+#[async_trait::async_trait]
+impl JobHandler for FixedHandler {
+    async fn execute(&self, _ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        self.0.clone()
+    }
+}
+
+/// Finishes with `output` once the gate is opened.
+struct Gated {
+    gate: Arc<Notify>,
+    output: serde_json::Value,
+}
+
+#[async_trait::async_trait]
+impl JobHandler for Gated {
+    async fn execute(&self, _ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        self.gate.notified().await;
+        Ok(Some(self.output.clone()))
+    }
+}
+
+fn executor(
+    repository: &Arc<MemoryJobs>,
+    handler: Arc<dyn JobHandler>,
+    lease_ttl: Duration,
+) -> Executor<MemoryJobs> {
+    Executor::new(
+        Arc::clone(repository),
+        HandlerRegistry::new().register(DEFAULT_JOB_TYPE, handler),
+        Duration::from_secs(60),
+        RetryPolicy::new(Backoff::Fixed { delay: Duration::from_secs(1) }),
+        Arc::new(SystemClock),
+        WORKER.to_string(),
+        lease_ttl,
+    )
+}
+
+/// Stores `job` and claims it for `WORKER`, as the orchestrator would.
+async fn claimed(repository: &MemoryJobs, job: Job) -> Job {
+    repository.insert_job(&job).await.unwrap();
+    let lease_expires_at = Utc::now() + chrono::Duration::seconds(60);
+    repository.claim_jobs(&[job], WORKER, lease_expires_at).await.unwrap().remove(0)
+}
+
+fn context(job: &Job) -> JobContext {
+    context_with_deadline(job, Duration::from_secs(5))
+}
+
+fn context_with_deadline(job: &Job, within: Duration) -> JobContext {
+    let (progress, _) = ProgressReporter::channel();
+    JobContext::new(
+        job,
+        Instant::now() + within,
+        CancellationToken::new(),
+        progress,
+        JobLogger::new(job, None, Arc::new(SystemClock)),
+    )
+}
+
+fn job() -> Job {
+    Job::new(Uuid::from_u128(1), serde_json::json!({ "n": 1 }), Utc::now())
+}
+
+#[tokio::test]
+async fn registry_dispatches_by_job_type() {
+    let registry = HandlerRegistry::new()
+        .register("ok", Arc::new(FixedHandler(Ok(Some(serde_json::json!({ "rows": 3 }))))))
+        .register("broken", Arc::new(FixedHandler(Err(Failure::system("boom")))));
+
+    let job = job();
+
+    assert_eq!(
+        registry.get("ok").unwrap().execute(context(&job)).await.unwrap(),
+        Some(serde_json::json!({ "rows": 3 }))
+    );
+    assert!(registry.get("broken").unwrap().execute(context(&job)).await.is_err());
+    assert!(registry.get("missing").is_none());
+    assert_eq!(registry.job_types(), vec!["broken", "ok"]);
+}
+
+#[tokio::test]
+async fn later_registration_replaces_earlier() {
+    let registry = HandlerRegistry::new()
+        .register("job", Arc::new(FixedHandler(Err(Failure::system("old")))))
+        .register("job", Arc::new(FixedHandler(Ok(None))));
+
+    assert!(registry.get("job").unwrap().execute(context(&job())).await.is_ok());
+    assert_eq!(registry.job_types(), vec!["job"]);
+}
+
+#[test]
+fn registry_keeps_per_type_timeouts() {
+    let registry = HandlerRegistry::new()
+        .register("plain", Arc::new(FixedHandler(Ok(None))))
+        .register_with_timeout("report", Arc::new(FixedHandler(Ok(None))), Duration::from_secs(600));
+
+    assert_eq!(registry.timeout("report"), Some(Duration::from_secs(600)));
+    assert_eq!(registry.timeout("plain"), None);
+    assert_eq!(registry.timeout("missing"), None);
+}
+
+#[test]
+fn most_specific_timeout_wins() {
+    let job = Some(Duration::from_secs(1));
+    let job_type = Some(Duration::from_secs(60));
+    let global = Duration::from_secs(5);
+
+    assert_eq!(
+        effective_timeout(job, job_type, global),
+        (Duration::from_secs(1), TimeoutSource::Job)
+    );
+    assert_eq!(
+        effective_timeout(None, job_type, global),
+        (Duration::from_secs(60), TimeoutSource::JobType)
+    );
+    assert_eq!(
+        effective_timeout(None, None, global),
+        (Duration::from_secs(5), TimeoutSource::Global)
+    );
+}
+
+#[tokio::test]
+async fn context_describes_the_current_attempt() {
+    let job = Job {
+        attempt: 2,
+        max_attempts: 4,
+        ..job().with_job_type("report")
+    };
+
+    let ctx = context(&job);
+
+    assert_eq!(ctx.job_id, job.id);
+    assert_eq!(ctx.job_type, "report");
+    assert_eq!(ctx.payload, serde_json::json!({ "n": 1 }));
+    assert_eq!(ctx.attempt, 3);
+    assert_eq!(ctx.max_attempts, 4);
+    assert!(ctx.remaining() <= Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn progress_keeps_latest_clamped_value() {
+    let (progress, mut updates) = ProgressReporter::channel();
+
+    progress.report(10);
+    progress.checkpoint(b"page=3".to_vec());
+    progress.clone().report(250);
+
+    assert!(updates.has_changed().unwrap());
+    assert_eq!(
+        *updates.borrow_and_update(),
+        Progress { percent: Some(100), checkpoint: Some(b"page=3".to_vec()) }
+    );
+}
+
+async fn run_subprocess(payload: serde_json::Value) -> Result<Option<serde_json::Value>, Failure> {
+    let job = Job::new(Uuid::from_u128(1), payload, Utc::now());
+    SubprocessJobHandler.execute(context(&job)).await
+}
+
+fn failure_kind(result: Result<Option<serde_json::Value>, Failure>) -> FailureKind {
+    result.expect_err("command should fail").kind
+}
+
+#[tokio::test]
+async fn subprocess_success_reports_exit_code() {
+    let result = run_subprocess(serde_json::json!({
+        "argv": ["sh", "-c", "read line && test \"$line\" = hello && test \"$GREETING\" = hi && test \"$(pwd)\" = /"],
+        "env": { "GREETING": "hi" },
+        "cwd": "/",
+        "stdin": "hello\n",
+    }))
+    .await;
+
+    assert_eq!(result.unwrap(), Some(serde_json::json!({ "exit_code": 0 })));
+}
+
+#[tokio::test]
+async fn subprocess_exit_codes_map_to_failure_kinds() {
+    let exit = |code: i32| serde_json::json!({ "argv": ["sh", "-c", format!("exit {code}")] });
+
+    assert_eq!(failure_kind(run_subprocess(exit(3)).await), FailureKind::UserError);
+    assert_eq!(failure_kind(run_subprocess(exit(75)).await), FailureKind::SystemError);
+
+    let killed = serde_json::json!({ "argv": ["sh", "-c", "kill -9 $$"] });
+    assert_eq!(failure_kind(run_subprocess(killed).await), FailureKind::SystemError);
+
+    let missing = serde_json::json!({ "argv": ["/nonexistent/command"] });
+    assert_eq!(failure_kind(run_subprocess(missing).await), FailureKind::SystemError);
+}
+
+#[tokio::test]
+async fn subprocess_rejects_invalid_payloads() {
+    let no_argv = serde_json::json!({ "command": "ls" });
+    assert_eq!(failure_kind(run_subprocess(no_argv).await), FailureKind::UserError);
+
+    let empty_argv = serde_json::json!({ "argv": [] });
+    assert_eq!(failure_kind(run_subprocess(empty_argv).await), FailureKind::UserError);
+}
+
+/// Runs a command with its log stored in memory; returns the outcome and
+/// the logged lines.
+async fn run_subprocess_logged(
+    payload: serde_json::Value,
+) -> (Result<Option<serde_json::Value>, Failure>, Vec<String>) {
+    let repository = Arc::new(MemoryJobs::default());
+    let (writer, sink) = LogWriter::new(Arc::clone(&repository), Duration::from_millis(10));
+    let writer = tokio::spawn(writer.run());
+
+    let job = Job::new(Uuid::from_u128(1), payload, Utc::now());
+    let (progress, _) = ProgressReporter::channel();
+    let ctx = JobContext::new(
+        &job,
+        Instant::now() + Duration::from_secs(5),
+        CancellationToken::new(),
+        progress,
+        JobLogger::new(&job, Some(sink), Arc::new(SystemClock)),
+    );
+
+    let result = SubprocessJobHandler.execute(ctx).await;
+    writer.await.unwrap();
+
+    let lines = repository.fetch_job_logs(job.id, 0, 100).await.unwrap();
+    (result, lines.into_iter().map(|line| line.message).collect())
+}
+
+#[tokio::test]
+async fn subprocess_output_that_is_not_utf8_is_kept() {
+    let (result, mut lines) = run_subprocess_logged(serde_json::json!({
+        "argv": ["sh", "-c", "printf 'caf\\351\\n'; printf 'after\\n'; printf 'oops\\377\\n' >&2"],
+    }))
+    .await;
+
+    // stdout and stderr are read concurrently.
+    lines.sort();
+
+    assert_eq!(result.unwrap(), Some(serde_json::json!({ "exit_code": 0 })));
+    assert_eq!(lines, ["after", "caf\u{FFFD}", "oops\u{FFFD}"]);
+}
+
+#[tokio::test]
+async fn subprocess_ends_when_the_command_exits_despite_background_children() {
+    let pid_file = std::env::temp_dir().join(format!("subprocess-{}.pid", Uuid::new_v4()));
+    let script = format!("sleep 30 & echo $! > {}; echo started", pid_file.display());
+
+    let started = Instant::now();
+    let (result, lines) = run_subprocess_logged(serde_json::json!({ "argv": ["sh", "-c", script] })).await;
+
+    assert_eq!(result.unwrap(), Some(serde_json::json!({ "exit_code": 0 })));
+    assert!(started.elapsed() < Duration::from_secs(2), "took {:?}", started.elapsed());
+    assert_eq!(lines, ["started"]);
+
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    std::fs::remove_file(&pid_file).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).unwrap_or_default();
+    assert!(stat.is_empty() || stat.contains(") Z "), "background child still alive: {stat}");
+}
+
+#[tokio::test]
+async fn abandoned_subprocess_has_its_process_group_killed() {
+    let pid_file = std::env::temp_dir().join(format!("subprocess-{}.pid", Uuid::new_v4()));
+    let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+
+    let run = run_subprocess(serde_json::json!({ "argv": ["sh", "-c", script] }));
+    assert!(tokio::time::timeout(Duration::from_millis(500), run).await.is_err());
+
+    let pid = std::fs::read_to_string(&pid_file).unwrap();
+    std::fs::remove_file(&pid_file).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Gone, or a zombie waiting for a reaper that is not us.
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).unwrap_or_default();
+    assert!(stat.is_empty() || stat.contains(") Z "), "grandchild still alive: {stat}");
+}
+
+/// One-shot HTTP stub: answers the first request with `status` after
+/// `delay` and yields the raw request it received.
+async fn stub_server(status: u16, delay: Duration) -> (String, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length: ").map(str::to_string))
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+
+                if request.len() >= end + 4 + length || n == 0 {
+                    break;
+                }
+            }
+        }
+
+        tokio::time::sleep(delay).await;
+
+        let body = r#"{"ok":true}"#;
+        let response = format!(
+            "HTTP/1.1 {status} Stub\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let _ = socket.write_all(response.as_bytes()).await;
+
+        String::from_utf8(request).unwrap()
+    });
+
+    (url, server)
+}
+
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request.lines().find_map(|line| {
+        let (key, value) = line.split_once(": ")?;
+        key.eq_ignore_ascii_case(name).then_some(value)
+    })
+}
+
+async fn run_webhook(
+    handler: &WebhookJobHandler,
+    payload: serde_json::Value,
+    within: Duration,
+) -> Result<Option<serde_json::Value>, Failure> {
+    let job = Job::new(Uuid::from_u128(7), payload, Utc::now());
+    handler.execute(context_with_deadline(&job, within)).await
+}
+
+#[tokio::test]
+async fn webhook_sends_rendered_signed_request() {
+    let (url, server) = stub_server(200, Duration::ZERO).await;
+    let handler = WebhookJobHandler::new(reqwest::Client::new()).with_signing_secret("s3cret");
+
+    let result = run_webhook(
+        &handler,
+        serde_json::json!({
+            "url": url,
+            "method": "put",
+            "headers": { "x-tenant": "acme" },
+            "body": { "job": "{{job_id}}", "attempt": "{{attempt}}" },
+        }),
+        Duration::from_secs(5),
+    )
+    .await;
+
+    assert_eq!(
+        result.unwrap(),
+        Some(serde_json::json!({ "status": 200, "body": { "ok": true } }))
+    );
+
+    let request = server.await.unwrap();
+    let (head, body) = request.split_once("\r\n\r\n").unwrap();
+
+    assert!(head.starts_with("PUT /hook HTTP/1.1"));
+    assert_eq!(header(head, "x-tenant"), Some("acme"));
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(body).unwrap(),
+        serde_json::json!({ "job": Uuid::from_u128(7).to_string(), "attempt": "1" })
+    );
+
+    let timestamp = header(head, TIMESTAMP_HEADER).unwrap();
+    assert_eq!(
+        header(head, SIGNATURE_HEADER),
+        Some(sign(b"s3cret", timestamp, body).as_str())
+    );
+}
+
+#[tokio::test]
+async fn webhook_statuses_map_to_failure_kinds() {
+    let handler = WebhookJobHandler::new(reqwest::Client::new());
+
+    for (status, kind, retryable) in [
+        (404, FailureKind::UserError, None),
+        (429, FailureKind::UserError, Some(true)),
+        (503, FailureKind::SystemError, None),
+    ] {
+        let (url, _server) = stub_server(status, Duration::ZERO).await;
+        let failure = run_webhook(&handler, serde_json::json!({ "url": url }), Duration::from_secs(5))
+            .await
+            .expect_err("non-2xx should fail");
+
+        assert_eq!((failure.kind, failure.retryable), (kind, retryable), "status {status}");
+    }
+}
+
+#[tokio::test]
+async fn webhook_connection_errors_and_deadlines() {
+    let handler = WebhookJobHandler::new(reqwest::Client::new());
+
+    let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", closed.local_addr().unwrap());
+    drop(closed);
+
+    let refused = run_webhook(&handler, serde_json::json!({ "url": url }), Duration::from_secs(5)).await;
+    assert_eq!(failure_kind(refused), FailureKind::SystemError);
+
+    let (url, _server) = stub_server(200, Duration::from_secs(5)).await;
+    let slow = run_webhook(&handler, serde_json::json!({ "url": url }), Duration::from_millis(200)).await;
+    assert_eq!(failure_kind(slow), FailureKind::Timeout);
+}
+
+#[tokio::test]
+async fn edits_to_a_running_job_do_not_cost_the_worker_its_lease() {
+    let repository = Arc::new(MemoryJobs::default());
+    let gate = Arc::new(Notify::new());
+    let handler = Arc::new(Gated { gate: Arc::clone(&gate), output: serde_json::json!("done") });
+    let executor = executor(&repository, handler, Duration::from_millis(60));
+
+    let job = claimed(&repository, job()).await;
+    let task = executor.spawn(job.clone());
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let version = repository.fetch_job(job.id).await.unwrap().version;
+    repository.update_job_priority(job.id, version, 9).await.unwrap();
+
+    // Heartbeats land after the edit.
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    gate.notify_one();
+    task.await.unwrap();
+
+    let stored = repository.job(job.id);
+    assert_eq!(stored.state, JobState::Succeeded);
+    assert_eq!(stored.priority, 9);
+    assert_eq!(stored.result, Some(serde_json::json!("done")));
+}
+
+#[tokio::test]
+async fn handler_result_is_stored_on_success() {
+    let repository = Arc::new(MemoryJobs::default());
+    let handler = Arc::new(FixedHandler(Ok(Some(serde_json::json!({ "rows": 3 })))));
+    let executor = executor(&repository, handler, Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    executor.spawn(job.clone()).await.unwrap();
+
+    let stored = repository.job(job.id);
+    assert_eq!(stored.state, JobState::Succeeded);
+    assert_eq!(stored.result, Some(serde_json::json!({ "rows": 3 })));
+    assert_eq!(stored.lease_owner, None);
+}
+
+/// Stops as soon as it is cancelled, then reports a result anyway.
+struct Cooperative;
+
+#[async_trait::async_trait]
+impl JobHandler for Cooperative {
+    async fn execute(&self, ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        ctx.cancellation.cancelled().await;
+        Ok(Some(serde_json::json!("late")))
+    }
+}
+
+/// Bumps the job's version, as a heartbeat or edit racing the cancel would.
+fn bump_version(jobs: &mut [Job]) {
+    jobs[0].version += 1;
+}
+
+#[tokio::test]
+async fn cancel_signals_the_handler_and_discards_its_result() {
+    let repository = Arc::new(MemoryJobs::default());
+    let executor = executor(&repository, Arc::new(Cooperative), Duration::from_secs(60))
+        .with_cancel_grace(Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    let task = executor.spawn(job.clone());
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    executor.cancel(job.id).await.unwrap();
+
+    // Well within the grace period: the handler stopped on its own.
+    tokio::time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+
+    let stored = repository.job(job.id);
+    assert_eq!(stored.state, JobState::Cancelled);
+    assert_eq!(stored.result, None);
+    assert_eq!(stored.lease_owner, None);
+}
+
+#[tokio::test]
+async fn handler_ignoring_cancel_is_aborted_after_the_grace_period() {
+    let repository = Arc::new(MemoryJobs::default());
+    let gate = Arc::new(Notify::new());
+    let handler = Arc::new(Gated { gate: Arc::clone(&gate), output: serde_json::json!("late") });
+    let executor = executor(&repository, handler, Duration::from_secs(60))
+        .with_cancel_grace(Duration::from_millis(50));
+
+    let job = claimed(&repository, job()).await;
+    let task = executor.spawn(job.clone());
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    executor.cancel(job.id).await.unwrap();
+
+    let aborted = tokio::time::timeout(Duration::from_secs(1), task).await.unwrap();
+    assert!(aborted.unwrap_err().is_cancelled());
+
+    // Too late: the task is gone.
+    gate.notify_one();
+    assert_eq!(repository.job(job.id).state, JobState::Cancelled);
+    assert_eq!(repository.job(job.id).result, None);
+}
+
+#[tokio::test]
+async fn late_result_loses_to_cancel() {
+    let repository = Arc::new(MemoryJobs::default());
+    let gate = Arc::new(Notify::new());
+    let handler = Arc::new(Gated { gate: Arc::clone(&gate), output: serde_json::json!("late") });
+    let executor = executor(&repository, handler, Duration::from_secs(60))
+        .with_cancel_grace(Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    let task = executor.spawn(job.clone());
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    executor.cancel(job.id).await.unwrap();
+    gate.notify_one();
+    task.await.unwrap();
+
+    let stored = repository.job(job.id);
+    assert_eq!(stored.state, JobState::Cancelled);
+    assert_eq!(stored.result, None);
+}
+
+#[tokio::test]
+async fn cancel_retries_when_the_version_moves() {
+    let repository = Arc::new(MemoryJobs::default());
+    let executor = executor(&repository, Arc::new(Cooperative), Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    repository.before_next_state_update(bump_version);
+
+    executor.cancel(job.id).await.unwrap();
+
+    assert_eq!(repository.job(job.id).state, JobState::Cancelled);
+}
+
+#[tokio::test]
+async fn cancel_gives_up_when_the_version_keeps_moving() {
+    let repository = Arc::new(MemoryJobs::default());
+    let executor = executor(&repository, Arc::new(Cooperative), Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    for _ in 0..3 {
+        repository.before_next_state_update(bump_version);
+    }
+
+    let err = executor.cancel(job.id).await.unwrap_err();
+
+    assert!(matches!(err, RepositoryError::VersionConflict { .. }), "{err:?}");
+    assert_eq!(repository.job(job.id).state, JobState::Running);
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_jobs_that_finish_within_the_drain_timeout() {
+    let repository = Arc::new(MemoryJobs::default());
+    let gate = Arc::new(Notify::new());
+    let handler = Arc::new(Gated { gate: Arc::clone(&gate), output: serde_json::json!("done") });
+    let executor = executor(&repository, handler, Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    let _task = executor.spawn(job.clone());
+
+    let opener = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        gate.notify_one();
+    });
+    executor.shutdown(Duration::from_secs(5)).await;
+    opener.await.unwrap();
+
+    let stored = repository.job(job.id);
+    assert_eq!(stored.state, JobState::Succeeded);
+    assert_eq!(stored.result, Some(serde_json::json!("done")));
+}
+
+#[tokio::test]
+async fn shutdown_releases_unfinished_jobs_without_counting_an_attempt() {
+    let repository = Arc::new(MemoryJobs::default());
+    let gate = Arc::new(Notify::new());
+    let handler = Arc::new(Gated { gate, output: serde_json::json!("never") });
+    let executor = executor(&repository, handler, Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    let _task = executor.spawn(job.clone());
+
+    tokio::time::timeout(Duration::from_secs(1), executor.shutdown(Duration::from_millis(50)))
+        .await
+        .unwrap();
+
+    let stored = repository.job(job.id);
+    assert_eq!(stored.state, JobState::Queued);
+    assert_eq!(stored.attempt, job.attempt);
+    assert_eq!(stored.lease_owner, None);
+    assert_eq!(repository.event_reasons(job.id).last().map(String::as_str), Some("shutdown"));
+}
+
+/// Reports progress 1..=40 every 5ms, then waits for the gate.
+struct Reporting {
+    gate: Arc<Notify>,
+}
+
+#[async_trait::async_trait]
+impl JobHandler for Reporting {
+    async fn execute(&self, ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        for percent in 1..=40 {
+            ctx.progress.report(percent);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        self.gate.notified().await;
+        Ok(None)
+    }
+}
+
+/// Checkpoints and fails on a first attempt; returns the checkpoint it was
+/// resumed from on a later one.
+struct Resuming;
+
+#[async_trait::async_trait]
+impl JobHandler for Resuming {
+    async fn execute(&self, ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        match ctx.checkpoint {
+            None => {
+                ctx.progress.checkpoint(b"page=3".to_vec());
+                Err(Failure::system("connection reset"))
+            }
+            Some(checkpoint) => Ok(Some(serde_json::json!(String::from_utf8(checkpoint).unwrap()))),
+        }
+    }
+}
+
+#[tokio::test]
+async fn progress_is_saved_at_most_once_per_interval() {
+    let repository = Arc::new(MemoryJobs::default());
+    let gate = Arc::new(Notify::new());
+    let handler = Arc::new(Reporting { gate: Arc::clone(&gate) });
+    let executor = executor(&repository, handler, Duration::from_secs(60))
+        .with_progress_interval(Duration::from_millis(100));
+
+    let job = claimed(&repository, job()).await;
+    let task = executor.spawn(job.clone());
+
+    tokio::time::sleep(Duration::from_millis(350)).await;
+
+    // Saved while still running, in a handful of writes rather than 40.
+    let running = repository.job(job.id);
+    assert_eq!(running.state, JobState::Running);
+    assert_eq!(running.progress, Some(40));
+    assert!(running.version - job.version <= 4, "{} saves", running.version - job.version);
+
+    gate.notify_one();
+    task.await.unwrap();
+    assert_eq!(repository.job(job.id).state, JobState::Succeeded);
+}
+
+#[tokio::test]
+async fn checkpoint_is_handed_to_the_next_attempt() {
+    let repository = Arc::new(MemoryJobs::default());
+    let executor = executor(&repository, Arc::new(Resuming), Duration::from_secs(60));
+
+    let job = claimed(&repository, job()).await;
+    executor.spawn(job.clone()).await.unwrap();
+
+    let retried = repository.job(job.id);
+    assert_eq!(retried.state, JobState::Queued);
+    assert_eq!(retried.checkpoint.as_deref(), Some(&b"page=3"[..]));
+
+    let lease_expires_at = Utc::now() + chrono::Duration::seconds(60);
+    let reclaimed = repository.claim_jobs(&[retried], WORKER, lease_expires_at).await.unwrap().remove(0);
+    executor.spawn(reclaimed).await.unwrap();
+
+    let stored = repository.job(job.id);
+    assert_eq!(stored.state, JobState::Succeeded);
+    assert_eq!(stored.result, Some(serde_json::json!("page=3")));
+}
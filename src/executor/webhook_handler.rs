@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, StatusCode};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use crate::domain::failure::Failure;
+use crate::executor::context::JobContext;
+use crate::executor::runner::JobHandler;
+
+/// Header carrying `sha256=<hex HMAC>` of `"<timestamp>.<body>"`.
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+/// Header carrying the Unix timestamp covered by the signature.
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+
+/// The request to send, taken from the job payload.
+#[derive(Debug, Deserialize)]
+pub struct WebhookSpec {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Sent as JSON. String values may contain `{{job_id}}` and
+    /// `{{attempt}}`, replaced before sending.
+    pub body: Option<serde_json::Value>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+/// Delivers the job payload to an HTTP endpoint.
+///
+/// Rules:
+/// 1. A 2xx response succeeds; the status and JSON response body, if any,
+///    become the job result.
+/// 2. A 4xx response is a user failure. 408 and 429 are still retried,
+///    since the receiver asks to be called again later.
+/// 3. A 5xx response or a connection error is a system failure.
+/// 4. Running out of time before a response is a timeout failure.
+/// 5. With a signing secret, every request carries an HMAC-SHA256
+///    signature of its timestamp and body.
+pub struct WebhookJobHandler {
+    client: Client,
+    signing_secret: Option<Vec<u8>>,
+}
+
+impl WebhookJobHandler {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            signing_secret: None,
+        }
+    }
+
+    pub fn with_signing_secret(mut self, secret: impl Into<Vec<u8>>) -> Self {
+        self.signing_secret = Some(secret.into());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl JobHandler for WebhookJobHandler {
+    async fn execute(&self, ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        let spec: WebhookSpec = serde_json::from_value(ctx.payload.clone())
+            .map_err(|err| Failure::user(format!("invalid webhook payload: {err}")))?;
+
+        let method = Method::from_bytes(spec.method.to_uppercase().as_bytes())
+            .map_err(|_| Failure::user(format!("invalid webhook method '{}'", spec.method)))?;
+
+        let body = spec
+            .body
+            .as_ref()
+            .map(|template| render(template, &ctx))
+            .map(|body| body.to_string())
+            .unwrap_or_default();
+
+        let mut request = self
+            .client
+            .request(method, &spec.url)
+            .timeout(ctx.remaining())
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+
+        for (name, value) in &spec.headers {
+            request = request.header(name, value);
+        }
+
+        if let Some(secret) = &self.signing_secret {
+            let timestamp = chrono::Utc::now().timestamp().to_string();
+            request = request
+                .header(TIMESTAMP_HEADER, &timestamp)
+                .header(SIGNATURE_HEADER, sign(secret, &timestamp, &body));
+        }
+
+        let request = request.body(body).build().map_err(|err| {
+            Failure::user(format!("invalid webhook request for {}: {err}", spec.url))
+        })?;
+
+        let response = tokio::select! {
+            response = self.client.execute(request) => response.map_err(|err| send_failure(&spec.url, err))?,
+            _ = ctx.cancellation.cancelled() => {
+                return Err(Failure::system(format!("webhook to {} stopped: job cancelled", spec.url)));
+            }
+        };
+
+        let status = response.status();
+        ctx.logger.info(&format!("{} responded {status}", spec.url));
+
+        if status.is_success() {
+            let body = response.json::<serde_json::Value>().await.ok();
+            return Ok(Some(serde_json::json!({ "status": status.as_u16(), "body": body })));
+        }
+
+        Err(status_failure(&spec.url, status))
+    }
+}
+
+fn status_failure(url: &str, status: StatusCode) -> Failure {
+    let reason = format!("{url} responded {status}");
+
+    match status {
+        StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS => {
+            Failure::user(reason).retryable()
+        }
+        status if status.is_server_error() => Failure::system(reason),
+        _ => Failure::user(reason),
+    }
+}
+
+fn send_failure(url: &str, err: reqwest::Error) -> Failure {
+    if err.is_timeout() {
+        Failure::timeout(format!("webhook to {url} timed out"))
+    } else {
+        Failure::system(format!("webhook to {url} failed: {err}"))
+    }
+}
+
+/// Hex HMAC-SHA256 of `"<timestamp>.<body>"`, prefixed with `sha256=`.
+pub fn sign(secret: &[u8], timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn render(template: &serde_json::Value, ctx: &JobContext) -> serde_json::Value {
+    match template {
+        serde_json::Value::String(value) => serde_json::Value::String(
+            value
+                .replace("{{job_id}}", &ctx.job_id.to_string())
+                .replace("{{attempt}}", &ctx.attempt.to_string()),
+        ),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(|item| render(item, ctx)).collect())
+        }
+        serde_json::Value::Object(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, ctx)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,14 @@
+pub mod api;
+pub mod config;
+pub mod domain;
+pub mod scheduler;
+pub mod dependencies;
+pub mod executor;
+pub mod logs;
+pub mod storage;
+pub mod recovery;
+pub mod recurring;
+pub mod retry;
+pub mod orchestrator;
+pub mod observability;
+pub mod errors;
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// A line emitted by a handler, not yet stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobLogEntry {
+    pub job_id: Uuid,
+    pub attempt: u32,
+    pub level: LogLevel,
+    pub message: String,
+    pub logged_at: DateTime<Utc>,
+}
+
+/// A stored line of a job's log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobLogLine {
+    /// Position in the job's log, starting at 0.
+    pub offset: i64,
+    pub attempt: u32,
+    pub level: LogLevel,
+    pub message: String,
+    pub logged_at: DateTime<Utc>,
+}
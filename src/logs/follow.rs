@@ -0,0 +1,78 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::logs::entry::JobLogLine;
+use crate::storage::repository::{JobLogRepository, JobRepository, RepositoryError};
+
+/// Lines fetched per poll at most, unless set with `with_page_size`.
+const PAGE_SIZE: i64 = 500;
+
+/// Tails a job's log from an offset, polling for new lines until the job
+/// has finished.
+pub struct LogFollower<R>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    repository: Arc<R>,
+    job_id: Uuid,
+    offset: i64,
+    page_size: i64,
+    poll_interval: Duration,
+}
+
+impl<R> LogFollower<R>
+where
+    R: JobRepository + JobLogRepository + Send + Sync + 'static,
+{
+    /// `poll_interval` should be at least the log writer's flush interval,
+    /// so lines written just before the job finished are not missed.
+    pub fn new(repository: Arc<R>, job_id: Uuid, offset: i64, poll_interval: Duration) -> Self {
+        Self {
+            repository,
+            job_id,
+            offset,
+            page_size: PAGE_SIZE,
+            poll_interval,
+        }
+    }
+
+    pub fn with_page_size(mut self, page_size: i64) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Offset of the next line to be returned.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Waits for lines past the current offset.
+    ///
+    /// Returns `None` once the job is in a final state and one more poll
+    /// found nothing new.
+    pub async fn next(&mut self) -> Result<Option<Vec<JobLogLine>>, RepositoryError> {
+        let mut finished = false;
+
+        loop {
+            let lines = self
+                .repository
+                .fetch_job_logs(self.job_id, self.offset, self.page_size)
+                .await?;
+
+            if !lines.is_empty() {
+                self.offset += lines.len() as i64;
+                return Ok(Some(lines));
+            }
+
+            if finished {
+                return Ok(None);
+            }
+
+            finished = self.repository.fetch_job(self.job_id).await?.state.is_terminal();
+            sleep(self.poll_interval).await;
+        }
+    }
+}
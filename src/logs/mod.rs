@@ -0,0 +1,10 @@
+pub mod entry;
+pub mod follow;
+pub mod writer;
+
+pub use entry::{JobLogEntry, JobLogLine, LogLevel};
+pub use follow::LogFollower;
+pub use writer::{LogSink, LogWriter};
+
+#[cfg(test)]
+mod tests;
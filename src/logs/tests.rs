@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::domain::clock::{Clock, SystemClock};
+use crate::domain::job::Job;
+use crate::executor::JobLogger;
+use crate::logs::{JobLogEntry, JobLogLine, LogLevel, LogWriter};
+use crate::storage::repository::{JobLogRepository, RepositoryError};
+
+#[derive(Default)]
+struct MemoryLogs {
+    batches: Mutex<Vec<Vec<JobLogEntry>>>,
+}
+
+impl MemoryLogs {
+    fn lines(&self) -> Vec<JobLogEntry> {
+        self.batches.lock().unwrap().concat()
+    }
+}
+
+#[async_trait::async_trait]
+impl JobLogRepository for MemoryLogs {
+    async fn append_job_logs(&self, entries: &[JobLogEntry]) -> Result<(), RepositoryError> {
+        self.batches.lock().unwrap().push(entries.to_vec());
+        Ok(())
+    }
+
+    async fn fetch_job_logs(
+        &self,
+        _job_id: Uuid,
+        _offset: i64,
+        _limit: i64,
+    ) -> Result<Vec<JobLogLine>, RepositoryError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Clock stopped at a fixed instant.
+struct FixedClock(DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+fn job() -> Job {
+    Job::new(Uuid::new_v4(), serde_json::json!({}), Utc::now())
+}
+
+#[tokio::test]
+async fn logger_lines_are_stored_in_order_with_their_attempt() {
+    let repository = Arc::new(MemoryLogs::default());
+    let (writer, sink) = LogWriter::new(Arc::clone(&repository), Duration::from_secs(60));
+    let writer = tokio::spawn(writer.run());
+
+    let mut job = job();
+    job.attempt = 1;
+    let logged_at = Utc.timestamp_opt(1_000, 0).unwrap();
+    let logger = JobLogger::new(&job, Some(sink), Arc::new(FixedClock(logged_at)));
+
+    logger.info("starting");
+    logger.warn("slow response");
+    logger.error("giving up");
+    drop(logger);
+
+    writer.await.unwrap();
+
+    let lines: Vec<_> = repository
+        .lines()
+        .into_iter()
+        .map(|entry| (entry.job_id, entry.attempt, entry.level, entry.message, entry.logged_at))
+        .collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            (job.id, 2, LogLevel::Info, "starting".to_string(), logged_at),
+            (job.id, 2, LogLevel::Warn, "slow response".to_string(), logged_at),
+            (job.id, 2, LogLevel::Error, "giving up".to_string(), logged_at),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn writer_flushes_on_its_interval() {
+    let repository = Arc::new(MemoryLogs::default());
+    let (writer, sink) = LogWriter::new(Arc::clone(&repository), Duration::from_millis(20));
+    tokio::spawn(writer.run());
+
+    JobLogger::new(&job(), Some(sink.clone()), Arc::new(SystemClock)).info("hello");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(repository.lines().len(), 1);
+    drop(sink);
+}
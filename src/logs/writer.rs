@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::warn;
+
+use crate::logs::entry::JobLogEntry;
+use crate::storage::repository::JobLogRepository;
+
+/// Lines buffered between handlers and the writer before new ones are
+/// dropped.
+pub const LOG_BUFFER_CAPACITY: usize = 10_000;
+
+/// Lines stored per insert at most.
+const MAX_BATCH: usize = 500;
+
+/// Sending end handed to job loggers. Sending never waits: when the writer
+/// falls behind, lines are dropped rather than slowing handlers down.
+#[derive(Clone)]
+pub struct LogSink {
+    sender: mpsc::Sender<JobLogEntry>,
+}
+
+impl LogSink {
+    pub fn send(&self, entry: JobLogEntry) {
+        if let Err(mpsc::error::TrySendError::Full(entry)) = self.sender.try_send(entry) {
+            warn!(job_id = %entry.job_id, "job log buffer full; line dropped");
+        }
+    }
+}
+
+/// Background task that stores job log lines in batches.
+///
+/// Lines are flushed every `flush_interval`, or sooner once a full batch
+/// is waiting. The writer exits after flushing once every sink is dropped.
+pub struct LogWriter<R>
+where
+    R: JobLogRepository + Send + Sync + 'static,
+{
+    repository: Arc<R>,
+    receiver: mpsc::Receiver<JobLogEntry>,
+    flush_interval: Duration,
+}
+
+impl<R> LogWriter<R>
+where
+    R: JobLogRepository + Send + Sync + 'static,
+{
+    pub fn new(repository: Arc<R>, flush_interval: Duration) -> (Self, LogSink) {
+        let (sender, receiver) = mpsc::channel(LOG_BUFFER_CAPACITY);
+
+        let writer = Self {
+            repository,
+            receiver,
+            flush_interval,
+        };
+
+        (writer, LogSink { sender })
+    }
+
+    pub async fn run(mut self) {
+        let mut flush = interval(self.flush_interval);
+        flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut batch = Vec::new();
+
+        loop {
+            tokio::select! {
+                received = self.receiver.recv() => match received {
+                    Some(entry) => {
+                        batch.push(entry);
+
+                        if batch.len() >= MAX_BATCH {
+                            self.flush(&mut batch).await;
+                        }
+                    }
+                    None => break,
+                },
+                _ = flush.tick() => self.flush(&mut batch).await,
+            }
+        }
+
+        self.flush(&mut batch).await;
+    }
+
+    async fn flush(&self, batch: &mut Vec<JobLogEntry>) {
+        if batch.is_empty() {
+            return;
+        }
+
+        // A failed insert loses the batch; job logs are diagnostics and must
+        // not back up into handlers.
+        if let Err(err) = self.repository.append_job_logs(batch).await {
+            warn!(lines = batch.len(), error = %err, "failed to store job log lines");
+        }
+
+        batch.clear();
+    }
+}
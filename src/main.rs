@@ -1,24 +1,24 @@
 use std::sync::Arc;
 
 use sqlx::postgres::PgPoolOptions;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
 use tracing_subscriber::EnvFilter;
 
-use crate::config::Config;
-use crate::executor::Executor;
-use crate::executor::sleep_handler::SleepJobHandler;
-use crate::orchestrator::Orchestrator;
-use crate::storage::PostgresJobRepository;
-
-mod api;
-mod config;
-mod domain;
-mod scheduler;
-mod executor;
-mod storage;
-mod recovery;
-mod orchestrator;
-mod observability;
-mod errors;
+use deterministic_job_scheduler::api::{self, ApiState};
+use deterministic_job_scheduler::config::Config;
+use deterministic_job_scheduler::domain::clock::{Clock, SystemClock};
+use deterministic_job_scheduler::domain::job::DEFAULT_JOB_TYPE;
+use deterministic_job_scheduler::executor::{Executor, HandlerRegistry};
+use deterministic_job_scheduler::executor::sleep_handler::SleepJobHandler;
+use deterministic_job_scheduler::executor::subprocess_handler::SubprocessJobHandler;
+use deterministic_job_scheduler::executor::webhook_handler::WebhookJobHandler;
+use deterministic_job_scheduler::logs::LogWriter;
+use deterministic_job_scheduler::orchestrator::Orchestrator;
+use deterministic_job_scheduler::recurring::Materializer;
+use deterministic_job_scheduler::retry::RetryPolicy;
+use deterministic_job_scheduler::storage::PostgresJobRepository;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -34,22 +34,122 @@ async fn main() -> anyhow::Result<()> {
         .await?;
 
     let repository = Arc::new(PostgresJobRepository::new(pool));
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let shutdown = CancellationToken::new();
+
+    let materializer = Materializer::new(
+        Arc::clone(&repository),
+        Arc::clone(&clock),
+        config.scheduler_tick_interval,
+        config.misfire_grace,
+    );
+    let materializer = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move { materializer.run(shutdown).await }
+    });
+
+    let (log_writer, log_sink) = LogWriter::new(Arc::clone(&repository), config.log_flush_interval);
+    let log_writer = tokio::spawn(log_writer.run());
+
+    let mut webhook_handler = WebhookJobHandler::new(reqwest::Client::new());
+    if let Some(secret) = &config.webhook_signing_secret {
+        webhook_handler = webhook_handler.with_signing_secret(secret.as_bytes());
+    }
+
+    // Untyped jobs keep running on the sleep handler.
+    let handlers = HandlerRegistry::new()
+        .register(DEFAULT_JOB_TYPE, Arc::new(SleepJobHandler))
+        .register("subprocess", Arc::new(SubprocessJobHandler))
+        .register("webhook", Arc::new(webhook_handler));
+    info!(job_types = ?handlers.job_types(), "registered job handlers");
 
-    let handler = Arc::new(SleepJobHandler);
     let executor = Arc::new(Executor::new(
         Arc::clone(&repository),
-        handler,
+        handlers,
         config.job_timeout,
-    ));
+        RetryPolicy::new(config.retry_backoff)
+            .with_classification(config.failure_classification),
+        Arc::clone(&clock),
+        config.worker_id,
+        config.lease_ttl,
+    )
+    .with_cancel_grace(config.cancel_grace)
+    .with_progress_interval(config.progress_interval)
+    .with_log_sink(log_sink));
 
-    let orchestrator = Orchestrator::new(
+    let mut orchestrator = Orchestrator::new(
         Arc::clone(&repository),
-        executor,
+        Arc::clone(&executor),
         config.max_concurrency,
         config.scheduler_tick_interval,
-    );
+        config.drain_timeout,
+        Arc::clone(&clock),
+    )
+    .with_queue_limits(config.queue_limits.clone())
+    .with_scheduling_policy(config.scheduling_policy.clone());
+    if let Some(instance_id) = &config.instance_id {
+        orchestrator = orchestrator.with_instance_id(instance_id);
+    }
+
+    orchestrator.recover().await?;
 
-    orchestrator.run().await;
+    let listener = TcpListener::bind(config.api_bind_addr).await?;
+    info!(addr = %config.api_bind_addr, "api listening");
+    let api = tokio::spawn(api::serve(
+        listener,
+        ApiState {
+            repository,
+            executor,
+            clock,
+            idempotency_ttl: config.idempotency_ttl,
+            log_poll_interval: config.log_flush_interval,
+        },
+        shutdown.clone(),
+    ));
+
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            info!("shutdown signal received");
+            shutdown.cancel();
+        }
+    });
+
+    orchestrator.run(shutdown).await;
+
+    api.await??;
+    materializer.await?;
+
+    // Dropping the orchestrator drops the last log sink, letting the writer
+    // flush what is left and exit.
+    drop(orchestrator);
+    log_writer.await?;
 
     Ok(())
 }
+
+/// Resolves on SIGTERM or Ctrl-C.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for Ctrl-C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -1,98 +1,336 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use tokio::time::sleep;
-use tracing::{info, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
 
+use crate::dependencies::{resolve_dependencies, Resolution};
+use crate::domain::failure::Failure;
+use crate::domain::job::Job;
+use crate::domain::clock::Clock;
 use crate::domain::state::JobState;
-use crate::scheduler::{select_jobs, SchedulerInput};
-use crate::storage::repository::JobRepository;
-use crate::executor::runner::{Executor, JobHandler};
+use crate::recovery::{lease_instance, reconcile_jobs, ForcedFailure, RecoveryRun};
+use crate::scheduler::{select_jobs, tenant_key, SchedulerInput, SchedulingPolicy};
+use crate::storage::repository::{FailGuard, JobRepository, RepositoryError};
+use crate::executor::runner::Executor;
 
-pub struct Orchestrator<R, H>
+/// Select-and-claim rounds per tick; rounds after the first only run to
+/// replace jobs another orchestrator claimed first.
+const MAX_CLAIM_ROUNDS: usize = 3;
+
+pub struct Orchestrator<R>
 where
     R: JobRepository + Send + Sync + 'static,
-    H: JobHandler + Send + Sync + 'static,
 {
     repository: Arc<R>,
-    executor: Arc<Executor<R, H>>,
+    executor: Arc<Executor<R>>,
     max_concurrency: usize,
+    queue_limits: HashMap<String, usize>,
+    policy: SchedulingPolicy,
     tick_interval: Duration,
+    drain_timeout: Duration,
+    clock: Arc<dyn Clock>,
+    instance_id: Option<String>,
 }
 
-impl<R, H> Orchestrator<R, H>
+impl<R> Orchestrator<R>
 where
     R: JobRepository + Send + Sync + 'static,
-    H: JobHandler + Send + Sync + 'static,
 {
     pub fn new(
         repository: Arc<R>,
-        executor: Arc<Executor<R, H>>,
+        executor: Arc<Executor<R>>,
         max_concurrency: usize,
         tick_interval: Duration,
+        drain_timeout: Duration,
+        clock: Arc<dyn Clock>,
     ) -> Self {
         Self {
             repository,
             executor,
             max_concurrency,
+            queue_limits: HashMap::new(),
+            policy: SchedulingPolicy::default(),
             tick_interval,
+            drain_timeout,
+            clock,
+            instance_id: None,
         }
     }
 
-    pub async fn run(&self) {
+    /// Caps how many jobs of each named queue run at once, within
+    /// `max_concurrency`. Queues without a limit share the global pool.
+    pub fn with_queue_limits(mut self, queue_limits: HashMap<String, usize>) -> Self {
+        self.queue_limits = queue_limits;
+        self
+    }
+
+    pub fn with_scheduling_policy(mut self, policy: SchedulingPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// This worker's identity across restarts, which the executor's lease
+    /// owner id was built from with `lease_owner`. It must not be shared
+    /// with any other live worker.
+    pub fn with_instance_id(mut self, instance_id: impl Into<String>) -> Self {
+        self.instance_id = Some(instance_id.into());
+        self
+    }
+
+    /// Startup recovery: force-fails Running jobs whose lease expired, and
+    /// persists a summary of the run. Call once before `run`.
+    ///
+    /// With an instance id, jobs leased by an earlier boot of this instance
+    /// are force-failed too, without waiting for their lease to expire:
+    /// that boot has crashed, since this one is running.
+    pub async fn recover(&self) -> Result<RecoveryRun, crate::orchestrator::error::OrchestrationError> {
+        let now = self.clock.now();
+        let running_jobs = self.repository.fetch_running_jobs().await?;
+        let outcome = reconcile_jobs(&running_jobs, |job| {
+            let leased_by_this_instance = self.instance_id.as_deref().is_some_and(|instance| {
+                job.lease_owner.as_deref().and_then(lease_instance) == Some(instance)
+            });
+
+            leased_by_this_instance || job.lease_expired(now)
+        });
+
+        let failures: Vec<_> = running_jobs
+            .iter()
+            .filter(|job| outcome.reconciled_job_ids.contains(&job.id))
+            .map(|job| {
+                let mut job = job.clone();
+                let failure = Failure::system("recovered after crash");
+
+                // Mirrors the attempt increment persisted by `recover_jobs`.
+                job.attempt += 1;
+                let decision = self.executor.retry_policy().decide(&job, &failure, now);
+
+                ForcedFailure { job_id: job.id, version: job.version, failure, decision }
+            })
+            .collect();
+
+        let run = self
+            .repository
+            .recover_jobs(self.executor.worker_id(), now, &failures, &outcome.skipped_job_ids)
+            .await?;
+
+        info!(
+            run_id = %run.id,
+            recovered = run.recovered_job_ids.len(),
+            skipped = run.skipped_job_ids.len(),
+            "startup recovery finished"
+        );
+
+        Ok(run)
+    }
+
+    /// Ticks until `shutdown` fires, then stops claiming jobs and drains
+    /// the executor for up to the drain timeout.
+    pub async fn run(&self, shutdown: CancellationToken) {
         loop {
             if let Err(err) = self.tick().await {
                 warn!(error = ?err, "orchestration tick failed");
             }
 
-            sleep(self.tick_interval).await;
+            tokio::select! {
+                _ = sleep(self.tick_interval) => {}
+                _ = shutdown.cancelled() => break,
+            }
         }
+
+        info!(drain_timeout = ?self.drain_timeout, "shutting down; draining running jobs");
+        self.executor.shutdown(self.drain_timeout).await;
+        info!("shutdown complete");
     }
 
-    async fn tick(&self) -> Result<(), crate::orchestrator::error::OrchestrationError> {
-        let queued_jobs = self.repository.fetch_queued_jobs().await?;
+    /// One scheduling pass: resolves blocked jobs, reaps expired leases and
+    /// claims due jobs for the free capacity.
+    pub(crate) async fn tick(&self) -> Result<(), crate::orchestrator::error::OrchestrationError> {
+        self.resolve_blocked_jobs().await?;
+
+        let now = self.clock.now();
         let running_jobs = self.repository.fetch_running_jobs().await?;
+        let reaped = self.reap_expired_leases(&running_jobs, now).await?;
+        let mut running_jobs: Vec<Job> = running_jobs
+            .into_iter()
+            .filter(|job| !reaped.contains(&job.id))
+            .collect();
+
+        for _ in 0..MAX_CLAIM_ROUNDS {
+            if self.claim_due_jobs(&running_jobs, now).await? == 0 {
+                break;
+            }
 
-        let running_count = running_jobs.len();
+            // Another orchestrator got to some of the selected jobs first;
+            // select again from fresh rows so the next eligible jobs take
+            // their place.
+            running_jobs = self.repository.fetch_running_jobs().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Selects due jobs for the free capacity, claims them and spawns the
+    /// ones won. Returns how many selected jobs were lost to another
+    /// orchestrator.
+    async fn claim_due_jobs(
+        &self,
+        running_jobs: &[Job],
+        now: DateTime<Utc>,
+    ) -> Result<usize, crate::orchestrator::error::OrchestrationError> {
+        let queued_jobs = self.repository.fetch_queued_jobs(now).await?;
+
+        let mut running_by_queue: HashMap<String, usize> = HashMap::new();
+        let mut running_by_tenant: HashMap<String, usize> = HashMap::new();
+        for job in running_jobs {
+            *running_by_queue.entry(job.queue.clone()).or_default() += 1;
+            *running_by_tenant.entry(tenant_key(job).to_string()).or_default() += 1;
+        }
 
         let decision = select_jobs(SchedulerInput {
             queued_jobs: &queued_jobs,
-            running_count,
+            running_count: running_jobs.len(),
             max_concurrency: self.max_concurrency,
+            running_by_queue: &running_by_queue,
+            queue_limits: &self.queue_limits,
+            running_by_tenant: &running_by_tenant,
+            policy: &self.policy,
+            now,
         });
 
         if decision.selected_job_ids.is_empty() {
-            return Ok(());
+            return Ok(0);
         }
 
         info!(
             selected = decision.selected_job_ids.len(),
-            running = running_count,
+            running = running_jobs.len(),
             "scheduler selected jobs"
         );
 
-        for job_id in decision.selected_job_ids {
+        let mut jobs_by_id: HashMap<_, _> = queued_jobs
+            .into_iter()
+            .map(|job| (job.id, job))
+            .collect();
+
+        let selected: Vec<_> = decision
+            .selected_job_ids
+            .iter()
+            .filter_map(|job_id| jobs_by_id.remove(job_id))
+            .collect();
+
+        let claimed = self
+            .repository
+            .claim_jobs(&selected, self.executor.worker_id(), now + self.executor.lease_ttl())
+            .await?;
+
+        let lost = selected.len() - claimed.len();
+        if lost > 0 {
+            info!(
+                selected = selected.len(),
+                claimed = claimed.len(),
+                "some selected jobs were claimed by another orchestrator"
+            );
+        }
+
+        for job in claimed {
+            self.executor.spawn(job);
+        }
+
+        Ok(lost)
+    }
+
+    /// Promotes, cancels or fails blocked jobs whose parents have settled.
+    async fn resolve_blocked_jobs(&self) -> Result<(), crate::orchestrator::error::OrchestrationError> {
+        for blocked in self.repository.fetch_blocked_jobs().await? {
+            let job_id = blocked.job.id;
+            let version = blocked.job.version;
+
+            let (to, failure) = match resolve_dependencies(
+                blocked.job.dependency_failure_policy,
+                &blocked.parent_states,
+            ) {
+                Resolution::Wait => continue,
+                Resolution::Promote => (JobState::Queued, None),
+                Resolution::Cancel => (JobState::Cancelled, None),
+                Resolution::Fail(failure) => (JobState::Failed, Some(failure)),
+            };
+
             let transitioned = self
                 .repository
-                .update_job_state(
-                    job_id,
-                    JobState::Queued,
-                    JobState::Running,
-                    None,
-                )
+                .update_job_state(job_id, version, JobState::Blocked, to, failure.as_ref())
                 .await;
 
-            if transitioned.is_err() {
-                warn!(
+            match transitioned {
+                Ok(_) => info!(job_id = %job_id, to = ?to, "resolved blocked job"),
+                // Changed concurrently, e.g. by another orchestrator or a cancel;
+                // the next tick sees the fresh row.
+                Err(RepositoryError::StaleState { actual, .. }) => debug!(
                     job_id = %job_id,
-                    "failed to transition job to running (likely raced)"
-                );
+                    actual = ?actual,
+                    "blocked job already left Blocked"
+                ),
+                Err(RepositoryError::VersionConflict { .. }) => debug!(
+                    job_id = %job_id,
+                    "blocked job changed since it was read"
+                ),
+                Err(RepositoryError::NotFound(_)) => debug!(
+                    job_id = %job_id,
+                    "blocked job no longer exists"
+                ),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fails Running jobs whose worker stopped heartbeating, applying the
+    /// retry policy. Returns the ids of the jobs reaped.
+    async fn reap_expired_leases(
+        &self,
+        running_jobs: &[Job],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>, crate::orchestrator::error::OrchestrationError> {
+        let outcome = reconcile_jobs(running_jobs, |job| job.lease_expired(now));
+        let mut reaped = Vec::new();
+
+        for job in running_jobs {
+            if !outcome.reconciled_job_ids.contains(&job.id) {
                 continue;
             }
 
-            self.executor.spawn(job_id);
+            let mut job = job.clone();
+
+            let failure = Failure::system(format!(
+                "lease expired (owner {})",
+                job.lease_owner.as_deref().unwrap_or("none")
+            ));
+
+            // Mirrors the attempt increment persisted by `fail_job`.
+            job.attempt += 1;
+            let decision = self.executor.retry_policy().decide(&job, &failure, now);
+
+            match self.repository.fail_job(job.id, FailGuard::Version(job.version), &failure, &decision).await {
+                Ok(_) => {
+                    reaped.push(job.id);
+                    warn!(job_id = %job.id, decision = %decision, "reaped job with expired lease");
+                }
+                // Heartbeat or completion landed first; the worker is alive.
+                Err(
+                    RepositoryError::StaleState { .. }
+                    | RepositoryError::VersionConflict { .. }
+                    | RepositoryError::NotFound(_),
+                ) => debug!(job_id = %job.id, "expired lease resolved concurrently"),
+                Err(err) => return Err(err.into()),
+            }
         }
 
-        Ok(())
+        Ok(reaped)
     }
 }
@@ -2,3 +2,6 @@ pub mod loop_;
 pub mod error;
 
 pub use loop_::Orchestrator;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::domain::clock::SystemClock;
+use crate::domain::failure::Failure;
+use crate::domain::job::{Job, DEFAULT_JOB_TYPE};
+use crate::domain::state::JobState;
+use crate::executor::runner::JobHandler;
+use crate::executor::{Executor, HandlerRegistry, JobContext};
+use crate::orchestrator::Orchestrator;
+use crate::recovery::lease_owner;
+use crate::retry::{Backoff, RetryPolicy};
+use crate::storage::memory::MemoryJobs;
+
+const WORKER: &str = "worker-a";
+
+/// Runs until cancelled.
+struct Parked;
+
+#[async_trait::async_trait]
+impl JobHandler for Parked {
+    async fn execute(&self, ctx: JobContext) -> Result<Option<serde_json::Value>, Failure> {
+        ctx.cancellation.cancelled().await;
+        Ok(None)
+    }
+}
+
+fn job(id: u128, priority: i32) -> Job {
+    Job {
+        priority,
+        ..Job::new(Uuid::from_u128(id), serde_json::json!({}), Utc::now())
+    }
+}
+
+fn orchestrator(repository: &Arc<MemoryJobs>, max_concurrency: usize) -> Orchestrator<MemoryJobs> {
+    let executor = Executor::new(
+        Arc::clone(repository),
+        HandlerRegistry::new().register(DEFAULT_JOB_TYPE, Arc::new(Parked)),
+        Duration::from_secs(60),
+        RetryPolicy::new(Backoff::Fixed { delay: Duration::from_secs(1) }),
+        Arc::new(SystemClock),
+        WORKER.to_string(),
+        Duration::from_secs(60),
+    );
+
+    Orchestrator::new(
+        Arc::clone(repository),
+        Arc::new(executor),
+        max_concurrency,
+        Duration::from_secs(1),
+        Duration::from_secs(1),
+        Arc::new(SystemClock),
+    )
+}
+
+fn owner(repository: &MemoryJobs, id: u128) -> (JobState, Option<String>) {
+    let job = repository.job(Uuid::from_u128(id));
+    (job.state, job.lease_owner)
+}
+
+#[tokio::test]
+async fn jobs_lost_to_another_orchestrator_are_replaced() {
+    let repository = Arc::new(MemoryJobs::with_jobs([job(1, 3), job(2, 2), job(3, 1), job(4, 0)]));
+
+    // Another orchestrator claims and finishes job 1 between our read and
+    // our claim.
+    repository.before_next_claim(|jobs| {
+        let won = &mut jobs[0];
+        won.state = JobState::Succeeded;
+        won.version += 1;
+    });
+
+    orchestrator(&repository, 2).tick().await.unwrap();
+
+    let mine = (JobState::Running, Some(WORKER.to_string()));
+    assert_eq!(owner(&repository, 1), (JobState::Succeeded, None));
+    assert_eq!(owner(&repository, 2), mine);
+    assert_eq!(owner(&repository, 3), mine);
+    assert_eq!(owner(&repository, 4), (JobState::Queued, None));
+}
+
+#[tokio::test]
+async fn replacements_respect_jobs_running_elsewhere() {
+    let repository = Arc::new(MemoryJobs::with_jobs([job(1, 3), job(2, 2), job(3, 1)]));
+
+    repository.before_next_claim(|jobs| {
+        let won = &mut jobs[0];
+        won.state = JobState::Running;
+        won.lease_owner = Some("worker-b".into());
+        won.version += 1;
+    });
+
+    orchestrator(&repository, 2).tick().await.unwrap();
+
+    assert_eq!(owner(&repository, 1), (JobState::Running, Some("worker-b".into())));
+    assert_eq!(owner(&repository, 2), (JobState::Running, Some(WORKER.into())));
+    assert_eq!(owner(&repository, 3), (JobState::Queued, None));
+}
+
+fn leased(id: u128, lease_owner: &str, lease_expires_at: chrono::DateTime<Utc>) -> Job {
+    Job {
+        state: JobState::Running,
+        lease_owner: Some(lease_owner.to_string()),
+        lease_expires_at: Some(lease_expires_at),
+        ..job(id, 0)
+    }
+}
+
+fn crash_leftovers() -> Arc<MemoryJobs> {
+    let live = Utc::now() + chrono::Duration::seconds(60);
+    let expired = Utc::now() - chrono::Duration::seconds(1);
+
+    Arc::new(MemoryJobs::with_jobs([
+        leased(1, &lease_owner("scheduler-1", Uuid::from_u128(100)), live),
+        leased(2, &lease_owner("scheduler-2", Uuid::from_u128(200)), live),
+        leased(3, &lease_owner("scheduler-2", Uuid::from_u128(201)), expired),
+    ]))
+}
+
+#[tokio::test]
+async fn startup_recovers_jobs_leased_by_an_earlier_boot_of_this_instance() {
+    let repository = crash_leftovers();
+
+    let run = orchestrator(&repository, 2)
+        .with_instance_id("scheduler-1")
+        .recover()
+        .await
+        .unwrap();
+
+    assert_eq!(run.recovered_job_ids, vec![Uuid::from_u128(1), Uuid::from_u128(3)]);
+    assert_eq!(owner(&repository, 1), (JobState::Queued, None));
+    assert_eq!(repository.job(Uuid::from_u128(1)).attempt, 1);
+    assert_eq!(owner(&repository, 2).0, JobState::Running);
+}
+
+#[tokio::test]
+async fn without_an_instance_id_startup_recovers_expired_leases_only() {
+    let repository = crash_leftovers();
+
+    let run = orchestrator(&repository, 2).recover().await.unwrap();
+
+    assert_eq!(run.recovered_job_ids, vec![Uuid::from_u128(3)]);
+    assert_eq!(owner(&repository, 1).0, JobState::Running);
+    assert_eq!(owner(&repository, 2).0, JobState::Running);
+}
@@ -1 +1,10 @@
+pub mod owner;
 pub mod reconcile;
+pub mod run;
+
+pub use owner::{lease_instance, lease_owner};
+pub use reconcile::{reconcile_jobs, RecoveryOutcome};
+pub use run::{ForcedFailure, RecoveryRun};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,19 @@
+use uuid::Uuid;
+
+/// Length of `-{boot id}` at the end of a lease owner id.
+const BOOT_SUFFIX_LEN: usize = 37;
+
+/// Lease owner id of one boot of `instance`: `{instance}-{boot id}`.
+pub fn lease_owner(instance: &str, boot_id: Uuid) -> String {
+    format!("{instance}-{boot_id}")
+}
+
+/// The instance a `lease_owner` id was built for, or `None` for ids of any
+/// other shape.
+pub fn lease_instance(lease_owner: &str) -> Option<&str> {
+    let split = lease_owner.len().checked_sub(BOOT_SUFFIX_LEN)?;
+    let (instance, suffix) = lease_owner.split_at_checked(split)?;
+    let boot_id = suffix.strip_prefix('-')?;
+
+    Uuid::try_parse(boot_id).ok().map(|_| instance)
+}
@@ -3,27 +3,31 @@ use uuid::Uuid;
 use crate::domain::job::Job;
 use crate::domain::state::JobState;
 
-/// Result of startup reconciliation.
+/// Result of a reconciliation pass.
 #[derive(Debug)]
 pub struct RecoveryOutcome {
     pub reconciled_job_ids: Vec<Uuid>,
     pub skipped_job_ids: Vec<Uuid>,
 }
 
-/// Pure recovery reconciliation logic.
+/// Pure recovery reconciliation logic, shared by startup recovery and the
+/// lease reaper.
 ///
 /// Rules:
 /// 1. Only jobs in Running are reconciled.
-/// 2. Running jobs must be force-failed by the orchestrator/storage layer.
-/// 3. Other states are untouched.
-/// 4. This operation is idempotent.
-pub fn reconcile_jobs(jobs: &[Job]) -> RecoveryOutcome {
+/// 2. A Running job is reconciled when `orphaned` holds for it, i.e. no
+///    live worker is running it any more.
+/// 3. Reconciled jobs must be force-failed by the orchestrator/storage
+///    layer, which applies the retry policy.
+/// 4. Other jobs are skipped and left untouched.
+/// 5. This operation is idempotent.
+pub fn reconcile_jobs(jobs: &[Job], orphaned: impl Fn(&Job) -> bool) -> RecoveryOutcome {
     let mut reconciled = Vec::new();
     let mut skipped = Vec::new();
 
     for job in jobs {
         match job.state {
-            JobState::Running => reconciled.push(job.id),
+            JobState::Running if orphaned(job) => reconciled.push(job.id),
             _ => skipped.push(job.id),
         }
     }
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::failure::Failure;
+use crate::retry::RetryDecision;
+
+/// A Running job to force-fail during startup recovery, with the retry
+/// decision to apply once it is Failed.
+#[derive(Debug, Clone)]
+pub struct ForcedFailure {
+    pub job_id: Uuid,
+    pub version: i64,
+    pub failure: Failure,
+    pub decision: RetryDecision,
+}
+
+/// Persisted summary of one startup recovery pass.
+#[derive(Debug, Clone)]
+pub struct RecoveryRun {
+    pub id: Uuid,
+    pub worker_id: String,
+    /// Jobs force-failed by this run.
+    pub recovered_job_ids: Vec<Uuid>,
+    /// Jobs left alone, including ones that changed before they could be
+    /// force-failed.
+    pub skipped_job_ids: Vec<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
@@ -1,21 +1,14 @@
-use chrono::Utc;
+use chrono::{TimeZone, Utc};
 use uuid::Uuid;
 
 use crate::domain::job::Job;
 use crate::domain::state::JobState;
-use crate::recovery::reconcile_jobs;
+use crate::recovery::{lease_instance, lease_owner, reconcile_jobs};
 
 fn job(id: u8, state: JobState) -> Job {
     Job {
-        id: Uuid::from_u128(id as u128),
-        payload: serde_json::json!({}),
-        priority: 0,
         state,
-        attempt: 0,
-        max_attempts: 3,
-        failure: None,
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
+        ..Job::new(Uuid::from_u128(id as u128), serde_json::json!({}), Utc::now())
     }
 }
 
@@ -28,7 +21,7 @@ fn reconciles_only_running_jobs() {
         job(4, JobState::Running),
     ];
 
-    let outcome = reconcile_jobs(&jobs);
+    let outcome = reconcile_jobs(&jobs, |_| true);
 
     assert_eq!(
         outcome.reconciled_job_ids,
@@ -40,3 +33,45 @@ fn reconciles_only_running_jobs() {
         vec![Uuid::from_u128(1), Uuid::from_u128(3)]
     );
 }
+
+#[test]
+fn reaps_only_running_jobs_with_expired_leases() {
+    let now = Utc.timestamp_opt(1_000, 0).unwrap();
+
+    let mut live = job(1, JobState::Running);
+    live.lease_expires_at = Some(Utc.timestamp_opt(1_001, 0).unwrap());
+
+    let mut expired = job(2, JobState::Running);
+    expired.lease_expires_at = Some(now);
+
+    let orphaned = job(3, JobState::Running);
+
+    let mut queued = job(4, JobState::Queued);
+    queued.lease_expires_at = Some(Utc.timestamp_opt(0, 0).unwrap());
+
+    let outcome = reconcile_jobs(&[live, expired, orphaned, queued], |job| job.lease_expired(now));
+
+    assert_eq!(
+        outcome.reconciled_job_ids,
+        vec![Uuid::from_u128(2), Uuid::from_u128(3)]
+    );
+
+    assert_eq!(
+        outcome.skipped_job_ids,
+        vec![Uuid::from_u128(1), Uuid::from_u128(4)]
+    );
+}
+
+#[test]
+fn lease_owner_ids_name_their_instance() {
+    let boot_id = Uuid::from_u128(7);
+
+    assert_eq!(lease_instance(&lease_owner("scheduler-1", boot_id)), Some("scheduler-1"));
+    assert_eq!(lease_instance(&lease_owner("scheduler-10", boot_id)), Some("scheduler-10"));
+    assert_eq!(lease_instance(&lease_owner("", boot_id)), Some(""));
+
+    // Ids not built by `lease_owner` belong to no instance.
+    assert_eq!(lease_instance("scheduler-1"), None);
+    assert_eq!(lease_instance(&format!("scheduler-1_{boot_id}")), None);
+    assert_eq!(lease_instance(&"é".repeat(30)), None);
+}
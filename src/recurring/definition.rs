@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// What to do with fire times that passed while no orchestrator was running.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MissedFirePolicy {
+    /// Drop missed fire times; only on-time fires produce jobs.
+    Skip,
+    /// Collapse all missed fire times into a single job.
+    FireOnce,
+    /// Produce one job per missed fire time.
+    CatchUpAll,
+}
+
+/// A cron-style definition that materializes concrete jobs.
+#[derive(Debug, Clone)]
+pub struct RecurringJob {
+    pub id: Uuid,
+    pub name: String,
+    /// Job type of every materialized job.
+    pub job_type: String,
+
+    /// Standard 5-field cron, or 6/7 fields with seconds and year.
+    pub cron_expression: String,
+    /// IANA timezone name the expression is evaluated in.
+    pub timezone: String,
+
+    /// Payload of each materialized job. String values may contain
+    /// `{{fire_time}}`, replaced with the RFC 3339 fire time.
+    pub payload_template: serde_json::Value,
+    pub priority: i32,
+    pub max_attempts: u32,
+
+    pub missed_fire_policy: MissedFirePolicy,
+    pub enabled: bool,
+
+    /// Latest fire time already materialized; None before the first fire.
+    pub last_fire_time: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::domain::clock::Clock;
+use crate::recurring::schedule::{materialize_job, plan_fires};
+use crate::storage::repository::{JobRepository, RecurringJobRepository, RepositoryError};
+
+/// Control loop that turns recurring definitions into concrete jobs.
+///
+/// Several materializers may run against the same database: the unique
+/// `(recurring_job_id, fire_time)` index makes each fire time produce
+/// exactly one job, and losers of that race simply move on.
+pub struct Materializer<R>
+where
+    R: JobRepository + RecurringJobRepository + Send + Sync + 'static,
+{
+    repository: Arc<R>,
+    clock: Arc<dyn Clock>,
+    tick_interval: Duration,
+    misfire_grace: Duration,
+}
+
+impl<R> Materializer<R>
+where
+    R: JobRepository + RecurringJobRepository + Send + Sync + 'static,
+{
+    pub fn new(
+        repository: Arc<R>,
+        clock: Arc<dyn Clock>,
+        tick_interval: Duration,
+        misfire_grace: Duration,
+    ) -> Self {
+        Self {
+            repository,
+            clock,
+            tick_interval,
+            misfire_grace,
+        }
+    }
+
+    /// Ticks until `shutdown` fires, so no jobs are materialized while the
+    /// executor drains.
+    pub async fn run(&self, shutdown: CancellationToken) {
+        loop {
+            if let Err(err) = self.tick().await {
+                warn!(error = ?err, "recurring materialization tick failed");
+            }
+
+            tokio::select! {
+                _ = sleep(self.tick_interval) => {}
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        info!("recurring materializer stopped");
+    }
+
+    async fn tick(&self) -> Result<(), RepositoryError> {
+        let now = self.clock.now();
+
+        for definition in self.repository.fetch_recurring_jobs().await? {
+            let plan = match plan_fires(&definition, now, self.misfire_grace) {
+                Ok(plan) => plan,
+                Err(err) => {
+                    warn!(
+                        recurring_job_id = %definition.id,
+                        error = %err,
+                        "skipping invalid recurring job definition"
+                    );
+                    continue;
+                }
+            };
+
+            for fire_time in &plan.fire_times {
+                let job = materialize_job(&definition, *fire_time, now);
+
+                match self.repository.insert_job(&job).await {
+                    Ok(_) => info!(
+                        recurring_job_id = %definition.id,
+                        job_id = %job.id,
+                        fire_time = %fire_time,
+                        "materialized recurring job"
+                    ),
+                    Err(RepositoryError::AlreadyExists) => debug!(
+                        recurring_job_id = %definition.id,
+                        fire_time = %fire_time,
+                        "fire time already materialized"
+                    ),
+                    Err(err) => return Err(err),
+                }
+            }
+
+            if let Some(fire_time) = plan.advance_to {
+                self.repository
+                    .advance_recurring_job(definition.id, fire_time)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
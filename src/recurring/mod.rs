@@ -0,0 +1,10 @@
+pub mod definition;
+pub mod schedule;
+pub mod materializer;
+
+pub use definition::{MissedFirePolicy, RecurringJob};
+pub use materializer::Materializer;
+pub use schedule::{materialize_job, plan_fires, FirePlan, ScheduleError};
+
+#[cfg(test)]
+mod tests;
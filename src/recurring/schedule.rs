@@ -0,0 +1,140 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use uuid::Uuid;
+
+use crate::domain::job::Job;
+use crate::recurring::definition::{MissedFirePolicy, RecurringJob};
+
+/// Upper bound on fire times materialized per definition per tick.
+pub const MAX_FIRES_PER_TICK: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("invalid cron expression `{expression}`: {reason}")]
+    InvalidCron { expression: String, reason: String },
+
+    #[error("unknown timezone `{0}`")]
+    InvalidTimezone(String),
+}
+
+/// Fire times to materialize for one definition at one point in time.
+#[derive(Debug, PartialEq)]
+pub struct FirePlan {
+    pub fire_times: Vec<DateTime<Utc>>,
+    /// New value for the definition's `last_fire_time`, if it moved.
+    pub advance_to: Option<DateTime<Utc>>,
+}
+
+/// Pure fire-time planning.
+///
+/// Rules:
+/// 1. Fire times are the schedule's times strictly after `last_fire_time`
+///    (or the definition's `created_at`) and at or before `now`.
+/// 2. A fire time older than `now - misfire_grace` is missed.
+/// 3. On-time fire times always produce a job.
+/// 4. Missed fire times are handled by `MissedFirePolicy`.
+/// 5. `advance_to` covers every fire time considered, including skipped ones.
+/// 6. At most `MAX_FIRES_PER_TICK` fire times are produced per call. Under
+///    `Skip` and `FireOnce` the missed fire times are jumped over rather
+///    than walked, so a long outage is settled in a single call.
+pub fn plan_fires(
+    definition: &RecurringJob,
+    now: DateTime<Utc>,
+    misfire_grace: Duration,
+) -> Result<FirePlan, ScheduleError> {
+    let schedule = parse_schedule(&definition.cron_expression)?;
+    let timezone = Tz::from_str(&definition.timezone)
+        .map_err(|_| ScheduleError::InvalidTimezone(definition.timezone.clone()))?;
+
+    let start = definition.last_fire_time.unwrap_or(definition.created_at);
+
+    let grace = chrono::Duration::from_std(misfire_grace).unwrap_or(chrono::Duration::MAX);
+    let missed_before = now.checked_sub_signed(grace).unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+    let fires_after = |after: DateTime<Utc>| -> Vec<DateTime<Utc>> {
+        schedule
+            .after(&after.with_timezone(&timezone))
+            .map(|fire_time| fire_time.with_timezone(&Utc))
+            .take_while(|fire_time| *fire_time <= now)
+            .take(MAX_FIRES_PER_TICK)
+            .collect()
+    };
+
+    let (fire_times, advance_to) = match definition.missed_fire_policy {
+        MissedFirePolicy::CatchUpAll => {
+            let fire_times = fires_after(start);
+            let advance_to = fire_times.last().copied();
+            (fire_times, advance_to)
+        }
+        MissedFirePolicy::Skip | MissedFirePolicy::FireOnce => {
+            // The latest missed fire time, found by stepping back once from
+            // `missed_before`; the ones before it are never looked at.
+            let last_missed = schedule
+                .after(&missed_before.with_timezone(&timezone))
+                .next_back()
+                .map(|fire_time| fire_time.with_timezone(&Utc))
+                .filter(|fire_time| *fire_time > start);
+
+            let on_time = fires_after(last_missed.unwrap_or(start));
+            let advance_to = on_time.last().copied().or(last_missed);
+            let catch_up = last_missed
+                .filter(|_| definition.missed_fire_policy == MissedFirePolicy::FireOnce);
+
+            (catch_up.into_iter().chain(on_time).collect(), advance_to)
+        }
+    };
+
+    Ok(FirePlan { fire_times, advance_to })
+}
+
+/// Builds the concrete job for one fire time of a definition.
+pub fn materialize_job(
+    definition: &RecurringJob,
+    fire_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Job {
+    let payload = render(&definition.payload_template, &fire_time.to_rfc3339());
+
+    Job {
+        priority: definition.priority,
+        max_attempts: definition.max_attempts,
+        run_at: fire_time,
+        recurring_job_id: Some(definition.id),
+        fire_time: Some(fire_time),
+        ..Job::new(Uuid::new_v4(), payload, now).with_job_type(&definition.job_type)
+    }
+}
+
+/// Accepts standard 5-field expressions by pinning seconds to zero.
+fn parse_schedule(expression: &str) -> Result<Schedule, ScheduleError> {
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {expression}")
+    } else {
+        expression.to_string()
+    };
+
+    Schedule::from_str(&normalized).map_err(|err| ScheduleError::InvalidCron {
+        expression: expression.to_string(),
+        reason: err.to_string(),
+    })
+}
+
+fn render(template: &serde_json::Value, fire_time: &str) -> serde_json::Value {
+    use serde_json::Value;
+
+    match template {
+        Value::String(s) => Value::String(s.replace("{{fire_time}}", fire_time)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| render(v, fire_time)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), render(v, fire_time)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::recurring::schedule::MAX_FIRES_PER_TICK;
+use crate::recurring::{materialize_job, plan_fires, MissedFirePolicy, RecurringJob};
+
+fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, hour, minute, 0).unwrap()
+}
+
+fn definition(cron: &str, policy: MissedFirePolicy, last_fire_time: DateTime<Utc>) -> RecurringJob {
+    RecurringJob {
+        id: Uuid::from_u128(1),
+        name: "report".into(),
+        job_type: "report".into(),
+        cron_expression: cron.into(),
+        timezone: "UTC".into(),
+        payload_template: serde_json::json!({ "scheduled_for": "{{fire_time}}" }),
+        priority: 2,
+        max_attempts: 5,
+        missed_fire_policy: policy,
+        enabled: true,
+        last_fire_time: Some(last_fire_time),
+        created_at: at(0, 0),
+        updated_at: at(0, 0),
+    }
+}
+
+const GRACE: Duration = Duration::from_secs(60);
+
+#[test]
+fn fires_on_time_without_missed_runs() {
+    let def = definition("*/15 * * * *", MissedFirePolicy::Skip, at(10, 0));
+
+    let plan = plan_fires(&def, at(10, 15), GRACE).unwrap();
+
+    assert_eq!(plan.fire_times, vec![at(10, 15)]);
+    assert_eq!(plan.advance_to, Some(at(10, 15)));
+
+    let plan = plan_fires(&def, at(10, 14), GRACE).unwrap();
+    assert!(plan.fire_times.is_empty());
+    assert_eq!(plan.advance_to, None);
+}
+
+#[test]
+fn missed_fire_policies() {
+    let now = at(11, 0);
+
+    let skip = plan_fires(
+        &definition("*/15 * * * *", MissedFirePolicy::Skip, at(10, 0)),
+        now,
+        GRACE,
+    )
+    .unwrap();
+    assert_eq!(skip.fire_times, vec![at(11, 0)]);
+    assert_eq!(skip.advance_to, Some(at(11, 0)));
+
+    let once = plan_fires(
+        &definition("*/15 * * * *", MissedFirePolicy::FireOnce, at(10, 0)),
+        now,
+        GRACE,
+    )
+    .unwrap();
+    assert_eq!(once.fire_times, vec![at(10, 45), at(11, 0)]);
+
+    let all = plan_fires(
+        &definition("*/15 * * * *", MissedFirePolicy::CatchUpAll, at(10, 0)),
+        now,
+        GRACE,
+    )
+    .unwrap();
+    assert_eq!(
+        all.fire_times,
+        vec![at(10, 15), at(10, 30), at(10, 45), at(11, 0)]
+    );
+}
+
+#[test]
+fn evaluates_cron_in_definition_timezone() {
+    let mut def = definition("0 9 * * *", MissedFirePolicy::CatchUpAll, at(0, 0));
+    def.timezone = "Europe/Berlin".into();
+
+    let plan = plan_fires(&def, at(12, 0), GRACE).unwrap();
+
+    assert_eq!(plan.fire_times, vec![at(8, 0)]);
+}
+
+#[test]
+fn rejects_invalid_definitions() {
+    let def = definition("not a cron", MissedFirePolicy::Skip, at(0, 0));
+    assert!(plan_fires(&def, at(1, 0), GRACE).is_err());
+
+    let mut def = definition("* * * * *", MissedFirePolicy::Skip, at(0, 0));
+    def.timezone = "Mars/Olympus".into();
+    assert!(plan_fires(&def, at(1, 0), GRACE).is_err());
+}
+
+#[test]
+fn materialized_job_carries_fire_time_and_rendered_payload() {
+    let def = definition("*/15 * * * *", MissedFirePolicy::Skip, at(10, 0));
+
+    let job = materialize_job(&def, at(10, 15), at(10, 16));
+
+    assert_eq!(job.recurring_job_id, Some(def.id));
+    assert_eq!(job.fire_time, Some(at(10, 15)));
+    assert_eq!(job.run_at, at(10, 15));
+    assert_eq!(job.job_type, "report");
+    assert_eq!(job.priority, 2);
+    assert_eq!(job.max_attempts, 5);
+    assert_eq!(
+        job.payload,
+        serde_json::json!({ "scheduled_for": "2024-01-01T10:15:00+00:00" })
+    );
+}
+
+#[test]
+fn long_outage_is_settled_in_one_call() {
+    // Every minute for thirty days: far more missed fires than one tick
+    // considers.
+    let last_fire_time = at(0, 0) - chrono::Duration::days(30);
+    let now = at(10, 0) + chrono::Duration::seconds(30);
+
+    let once = definition("* * * * *", MissedFirePolicy::FireOnce, last_fire_time);
+    let plan = plan_fires(&once, now, GRACE).unwrap();
+    assert_eq!(plan.fire_times, vec![at(9, 59), at(10, 0)]);
+    assert_eq!(plan.advance_to, Some(at(10, 0)));
+
+    let skip = definition("* * * * *", MissedFirePolicy::Skip, last_fire_time);
+    let plan = plan_fires(&skip, now, GRACE).unwrap();
+    assert_eq!(plan.fire_times, vec![at(10, 0)]);
+    assert_eq!(plan.advance_to, Some(at(10, 0)));
+
+    let all = definition("* * * * *", MissedFirePolicy::CatchUpAll, last_fire_time);
+    let plan = plan_fires(&all, now, GRACE).unwrap();
+    assert_eq!(plan.fire_times.len(), MAX_FIRES_PER_TICK);
+    assert_eq!(plan.advance_to, plan.fire_times.last().copied());
+}
+
+#[test]
+fn fire_at_the_grace_boundary_is_on_time() {
+    let skip = definition("*/15 * * * *", MissedFirePolicy::Skip, at(10, 0));
+    let plan = plan_fires(&skip, at(11, 0), Duration::from_secs(15 * 60)).unwrap();
+    assert_eq!(plan.fire_times, vec![at(10, 45), at(11, 0)]);
+}
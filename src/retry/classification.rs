@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::failure::{Failure, FailureKind};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Retryability {
+    Retryable,
+    Terminal,
+}
+
+/// Maps each `FailureKind` to whether it may be retried.
+///
+/// Kinds missing from the table fall back to `Retryable`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FailureClassification(BTreeMap<FailureKind, Retryability>);
+
+impl FailureClassification {
+    /// Empty table: every kind is retryable.
+    pub fn retry_all() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    pub fn with(mut self, kind: FailureKind, retryability: Retryability) -> Self {
+        self.0.insert(kind, retryability);
+        self
+    }
+
+    pub fn get(&self, kind: FailureKind) -> Option<Retryability> {
+        self.0.get(&kind).copied()
+    }
+
+    pub fn classify(&self, kind: FailureKind) -> Retryability {
+        self.get(kind).unwrap_or(Retryability::Retryable)
+    }
+}
+
+impl Default for FailureClassification {
+    /// A bad payload fails the same way on every attempt; everything else
+    /// may be transient.
+    fn default() -> Self {
+        Self::retry_all()
+            .with(FailureKind::UserError, Retryability::Terminal)
+            .with(FailureKind::SystemError, Retryability::Retryable)
+            .with(FailureKind::Timeout, Retryability::Retryable)
+    }
+}
+
+/// Resolves the retryability of a failure.
+///
+/// Precedence, most specific first:
+/// 1. The handler override on the failure itself.
+/// 2. The per-job classification table.
+/// 3. The policy-wide classification table.
+pub fn classify(
+    failure: &Failure,
+    job_table: Option<&FailureClassification>,
+    default_table: &FailureClassification,
+) -> Retryability {
+    if let Some(retryable) = failure.retryable {
+        return if retryable {
+            Retryability::Retryable
+        } else {
+            Retryability::Terminal
+        };
+    }
+
+    job_table
+        .and_then(|table| table.get(failure.kind))
+        .unwrap_or_else(|| default_table.classify(failure.kind))
+}
@@ -0,0 +1,8 @@
+pub mod classification;
+pub mod policy;
+
+pub use classification::{FailureClassification, Retryability};
+pub use policy::{Backoff, RetryDecision, RetryPolicy};
+
+#[cfg(test)]
+mod tests;
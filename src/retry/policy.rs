@@ -0,0 +1,159 @@
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::failure::{Failure, FailureKind};
+use crate::domain::job::Job;
+use crate::retry::classification::{classify, FailureClassification, Retryability};
+
+/// Delay strategy between consecutive attempts of the same job.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Backoff {
+    /// Same delay after every failed attempt.
+    Fixed { delay: Duration },
+    /// `base * 2^(attempt - 1)`, capped at `max`.
+    Exponential { base: Duration, max: Duration },
+    /// Exponential delay where the upper half is jittered.
+    /// The jitter is derived from the job id, so it is reproducible.
+    ExponentialJitter { base: Duration, max: Duration },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RetryPolicy {
+    pub backoff: Backoff,
+    pub classification: FailureClassification,
+}
+
+/// Outcome of a retry evaluation for a failed attempt.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RetryDecision {
+    /// Move the job back to Queued, not to be run before `next_run_at`.
+    Retry {
+        attempt: u32,
+        max_attempts: u32,
+        next_run_at: DateTime<Utc>,
+    },
+    /// No attempts left; the job stays Failed.
+    Exhausted {
+        attempt: u32,
+        max_attempts: u32,
+    },
+    /// The failure is classified as terminal; the job stays Failed
+    /// regardless of remaining attempts.
+    Terminal {
+        attempt: u32,
+        kind: FailureKind,
+    },
+}
+
+impl RetryPolicy {
+    pub fn new(backoff: Backoff) -> Self {
+        Self {
+            backoff,
+            classification: FailureClassification::default(),
+        }
+    }
+
+    pub fn with_classification(mut self, classification: FailureClassification) -> Self {
+        self.classification = classification;
+        self
+    }
+
+    /// Pure retry decision.
+    ///
+    /// Rules:
+    /// 1. `job.attempt` must already count the attempt that just failed.
+    /// 2. Terminal failures are never retried (see `classification::classify`).
+    /// 3. Otherwise a job is retried only while `Job::can_retry` holds.
+    /// 4. The delay depends only on the policy, the job id and the attempt,
+    ///    so the same inputs always produce the same decision.
+    pub fn decide(&self, job: &Job, failure: &Failure, now: DateTime<Utc>) -> RetryDecision {
+        let retryability = classify(
+            failure,
+            job.failure_classification.as_ref(),
+            &self.classification,
+        );
+
+        if retryability == Retryability::Terminal {
+            return RetryDecision::Terminal {
+                attempt: job.attempt,
+                kind: failure.kind,
+            };
+        }
+
+        if !job.can_retry() {
+            return RetryDecision::Exhausted {
+                attempt: job.attempt,
+                max_attempts: job.max_attempts,
+            };
+        }
+
+        let delay = self.delay_for(job.id, job.attempt);
+        let delay = chrono::Duration::from_std(delay)
+            .unwrap_or(chrono::Duration::MAX);
+
+        RetryDecision::Retry {
+            attempt: job.attempt,
+            max_attempts: job.max_attempts,
+            next_run_at: now
+                .checked_add_signed(delay)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    /// Delay before the next run, given the number of failed attempts so far.
+    pub fn delay_for(&self, job_id: Uuid, attempt: u32) -> Duration {
+        match self.backoff {
+            Backoff::Fixed { delay } => delay,
+            Backoff::Exponential { base, max } => exponential(base, max, attempt),
+            Backoff::ExponentialJitter { base, max } => {
+                let delay = exponential(base, max, attempt);
+                let half = delay / 2;
+                let spread = (delay - half).as_millis() as u64;
+                let jitter = seed(job_id, attempt) % (spread + 1);
+
+                half + Duration::from_millis(jitter)
+            }
+        }
+    }
+}
+
+impl fmt::Display for RetryDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetryDecision::Retry { attempt, max_attempts, next_run_at } => write!(
+                f,
+                "retry scheduled after attempt {attempt}/{max_attempts}, next run at {}",
+                next_run_at.to_rfc3339()
+            ),
+            RetryDecision::Exhausted { attempt, max_attempts } => write!(
+                f,
+                "retries exhausted after attempt {attempt}/{max_attempts}"
+            ),
+            RetryDecision::Terminal { attempt, kind } => write!(
+                f,
+                "terminal {kind:?} failure on attempt {attempt}, not retried"
+            ),
+        }
+    }
+}
+
+fn exponential(base: Duration, max: Duration, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1);
+    let factor = 1u32.checked_shl(exponent).unwrap_or(u32::MAX);
+
+    base.checked_mul(factor).unwrap_or(max).min(max)
+}
+
+/// SplitMix64 over the job id and attempt number.
+fn seed(job_id: Uuid, attempt: u32) -> u64 {
+    let id = job_id.as_u128();
+    let mut z = (id as u64) ^ ((id >> 64) as u64) ^ u64::from(attempt);
+
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
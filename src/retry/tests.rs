@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::domain::failure::{Failure, FailureKind};
+use crate::domain::job::Job;
+use crate::domain::state::JobState;
+use crate::retry::{Backoff, FailureClassification, RetryDecision, RetryPolicy, Retryability};
+
+fn failed_job(id: u8, attempt: u32, max_attempts: u32) -> Job {
+    Job {
+        state: JobState::Failed,
+        attempt,
+        max_attempts,
+        ..Job::new(
+            Uuid::from_u128(id as u128),
+            serde_json::json!({}),
+            Utc.timestamp_opt(0, 0).unwrap(),
+        )
+    }
+}
+
+#[test]
+fn fixed_backoff_requeues_until_attempts_run_out() {
+    let policy = RetryPolicy::new(Backoff::Fixed {
+        delay: Duration::from_secs(10),
+    });
+    let now = Utc.timestamp_opt(1_000, 0).unwrap();
+
+    assert_eq!(
+        policy.decide(&failed_job(1, 2, 3), &Failure::system("boom"), now),
+        RetryDecision::Retry {
+            attempt: 2,
+            max_attempts: 3,
+            next_run_at: Utc.timestamp_opt(1_010, 0).unwrap(),
+        }
+    );
+
+    assert_eq!(
+        policy.decide(&failed_job(1, 3, 3), &Failure::system("boom"), now),
+        RetryDecision::Exhausted {
+            attempt: 3,
+            max_attempts: 3,
+        }
+    );
+}
+
+#[test]
+fn exponential_backoff_doubles_and_caps() {
+    let policy = RetryPolicy::new(Backoff::Exponential {
+        base: Duration::from_secs(1),
+        max: Duration::from_secs(5),
+    });
+    let id = Uuid::from_u128(1);
+
+    assert_eq!(policy.delay_for(id, 1), Duration::from_secs(1));
+    assert_eq!(policy.delay_for(id, 2), Duration::from_secs(2));
+    assert_eq!(policy.delay_for(id, 3), Duration::from_secs(4));
+    assert_eq!(policy.delay_for(id, 4), Duration::from_secs(5));
+    assert_eq!(policy.delay_for(id, 64), Duration::from_secs(5));
+}
+
+#[test]
+fn jitter_is_deterministic_per_job() {
+    let policy = RetryPolicy::new(Backoff::ExponentialJitter {
+        base: Duration::from_secs(8),
+        max: Duration::from_secs(60),
+    });
+
+    for attempt in 1..6 {
+        let a = policy.delay_for(Uuid::from_u128(7), attempt);
+        let b = policy.delay_for(Uuid::from_u128(7), attempt);
+        assert_eq!(a, b);
+
+        let ceiling = Duration::from_secs(8 << (attempt - 1)).min(Duration::from_secs(60));
+        assert!(a >= ceiling / 2 && a <= ceiling);
+    }
+
+    assert_ne!(
+        policy.delay_for(Uuid::from_u128(7), 3),
+        policy.delay_for(Uuid::from_u128(8), 3)
+    );
+}
+
+#[test]
+fn user_errors_are_terminal_by_default() {
+    let policy = RetryPolicy::new(Backoff::Fixed {
+        delay: Duration::from_secs(1),
+    });
+    let now = Utc.timestamp_opt(0, 0).unwrap();
+
+    assert_eq!(
+        policy.decide(&failed_job(1, 1, 3), &Failure::user("bad payload"), now),
+        RetryDecision::Terminal {
+            attempt: 1,
+            kind: FailureKind::UserError,
+        }
+    );
+
+    assert!(matches!(
+        policy.decide(&failed_job(1, 1, 3), &Failure::timeout("slow"), now),
+        RetryDecision::Retry { .. }
+    ));
+}
+
+#[test]
+fn handler_override_beats_job_table_beats_policy_table() {
+    let policy = RetryPolicy::new(Backoff::Fixed {
+        delay: Duration::from_secs(1),
+    });
+    let now = Utc.timestamp_opt(0, 0).unwrap();
+
+    let mut job = failed_job(1, 1, 3);
+    job.failure_classification = Some(
+        FailureClassification::retry_all()
+            .with(FailureKind::UserError, Retryability::Retryable)
+            .with(FailureKind::SystemError, Retryability::Terminal),
+    );
+
+    assert!(matches!(
+        policy.decide(&job, &Failure::user("flaky upstream validation"), now),
+        RetryDecision::Retry { .. }
+    ));
+
+    assert!(matches!(
+        policy.decide(&job, &Failure::system("disk full"), now),
+        RetryDecision::Terminal { .. }
+    ));
+
+    assert!(matches!(
+        policy.decide(&job, &Failure::user("bad payload").non_retryable(), now),
+        RetryDecision::Terminal { .. }
+    ));
+
+    assert!(matches!(
+        policy.decide(&job, &Failure::system("disk full").retryable(), now),
+        RetryDecision::Retry { .. }
+    ));
+}
+
+#[test]
+fn classification_round_trips_through_json() {
+    let table = FailureClassification::retry_all()
+        .with(FailureKind::Timeout, Retryability::Terminal);
+
+    let json = serde_json::to_value(&table).unwrap();
+    assert_eq!(json, serde_json::json!({ "timeout": "terminal" }));
+
+    let parsed: FailureClassification = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, table);
+}
@@ -0,0 +1,140 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use uuid::Uuid;
+
+use crate::domain::job::Job;
+use crate::scheduler::scheduler::{tenant_key, QueueSlots};
+
+/// Tenant weights for `SchedulingPolicy::FairShare`. A tenant with weight
+/// 2 gets twice the running jobs of a tenant with weight 1 while both have
+/// work waiting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairShare {
+    weights: HashMap<String, u32>,
+    default_weight: u32,
+}
+
+impl FairShare {
+    /// Every tenant weighs `default_weight`; zero is treated as 1.
+    pub fn new(default_weight: u32) -> Self {
+        Self {
+            weights: HashMap::new(),
+            default_weight: default_weight.max(1),
+        }
+    }
+
+    /// Overrides the weight of `tenant`; zero is treated as 1.
+    pub fn with_weight(mut self, tenant: impl Into<String>, weight: u32) -> Self {
+        self.weights.insert(tenant.into(), weight.max(1));
+        self
+    }
+
+    pub fn weight(&self, tenant: &str) -> u32 {
+        self.weights
+            .get(tenant)
+            .copied()
+            .unwrap_or(self.default_weight)
+    }
+}
+
+impl Default for FairShare {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+struct Tenant<'a> {
+    key: &'a str,
+    weight: u32,
+    /// Running plus selected so far.
+    allocated: usize,
+    jobs: Vec<&'a Job>,
+    next: usize,
+}
+
+impl<'a> Tenant<'a> {
+    fn head(&self) -> Option<&'a Job> {
+        self.jobs.get(self.next).copied()
+    }
+
+    /// Compares `allocated / weight` exactly.
+    fn cmp_share(&self, other: &Tenant) -> Ordering {
+        let lhs = self.allocated as u128 * other.weight as u128;
+        let rhs = other.allocated as u128 * self.weight as u128;
+        lhs.cmp(&rhs)
+    }
+}
+
+/// Stride scheduling over tenants, seeded with what each tenant already
+/// runs.
+///
+/// Rules:
+/// 1. Within a tenant, jobs go in priority DESC, created_at ASC, id ASC
+///    order.
+/// 2. Each free slot goes to the tenant with waiting jobs whose
+///    `(running + selected) / weight` is lowest. Ties go to the tenant
+///    whose next job orders first by rule 1, then to the lower tenant key.
+/// 3. A job of a full queue is skipped without charging its tenant; a
+///    tenant with no jobs left drops out.
+/// 4. The result depends on the set of candidates, not their order.
+pub(crate) fn select_fair_share<'a>(
+    candidates: Vec<&'a Job>,
+    capacity: usize,
+    slots: &mut QueueSlots<'a>,
+    running_by_tenant: &HashMap<String, usize>,
+    shares: &FairShare,
+) -> Vec<Uuid> {
+    let mut by_tenant: BTreeMap<&str, Vec<&Job>> = BTreeMap::new();
+    for job in candidates {
+        by_tenant.entry(tenant_key(job)).or_default().push(job);
+    }
+
+    let mut tenants: Vec<Tenant> = by_tenant
+        .into_iter()
+        .map(|(key, mut jobs)| {
+            jobs.sort_by(|a, b| job_order(a, b));
+
+            Tenant {
+                key,
+                weight: shares.weight(key),
+                allocated: running_by_tenant.get(key).copied().unwrap_or(0),
+                jobs,
+                next: 0,
+            }
+        })
+        .collect();
+
+    let mut selected = Vec::new();
+
+    while selected.len() < capacity {
+        let Some(tenant) = tenants
+            .iter_mut()
+            .filter(|tenant| tenant.head().is_some())
+            .min_by(|a, b| {
+                a.cmp_share(b)
+                    .then_with(|| job_order(a.head().unwrap(), b.head().unwrap()))
+                    .then_with(|| a.key.cmp(b.key))
+            })
+        else {
+            break;
+        };
+
+        let job = tenant.head().unwrap();
+        tenant.next += 1;
+
+        if slots.take(&job.queue) {
+            tenant.allocated += 1;
+            selected.push(job.id);
+        }
+    }
+
+    selected
+}
+
+fn job_order(a: &Job, b: &Job) -> Ordering {
+    b.priority
+        .cmp(&a.priority)
+        .then_with(|| a.created_at.cmp(&b.created_at))
+        .then_with(|| a.id.cmp(&b.id))
+}
@@ -1,3 +1,11 @@
+#[allow(clippy::module_inception)]
 pub mod scheduler;
+pub mod fair_share;
 
-pub use scheduler::{select_jobs, SchedulerInput};
+pub use fair_share::FairShare;
+pub use scheduler::{select_jobs, tenant_key, SchedulerDecision, SchedulerInput, SchedulingPolicy};
+
+#[cfg(test)]
+mod tests;
+#[cfg(test)]
+mod proptests;
@@ -0,0 +1,255 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, TimeZone, Utc};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+use crate::domain::job::Job;
+use crate::domain::state::JobState;
+use crate::scheduler::{
+    select_jobs, tenant_key, FairShare, SchedulerDecision, SchedulerInput, SchedulingPolicy,
+};
+
+const TENANTS: [&str; 4] = ["", "acme", "globex", "initech"];
+const QUEUES: [&str; 3] = ["default", "bulk", "urgent"];
+
+fn now() -> DateTime<Utc> {
+    Utc.timestamp_opt(1_000, 0).unwrap()
+}
+
+/// Jobs with distinct ids and created_at, spread over tenants and queues.
+fn jobs() -> impl Strategy<Value = Vec<Job>> {
+    prop::collection::vec(
+        (
+            0..TENANTS.len(),
+            0..QUEUES.len(),
+            -2i32..3,
+            any::<bool>(),
+            any::<bool>(),
+        ),
+        0..40,
+    )
+    .prop_map(|specs| {
+        specs
+            .into_iter()
+            .enumerate()
+            .map(|(index, (tenant, queue, priority, due, queued))| {
+                let created_at = Utc.timestamp_opt(index as i64, 0).unwrap();
+                let mut job = Job::new(
+                    Uuid::from_u128(index as u128 + 1),
+                    serde_json::json!({}),
+                    created_at,
+                )
+                .with_queue(QUEUES[queue]);
+
+                if !TENANTS[tenant].is_empty() {
+                    job = job.with_tenant(TENANTS[tenant]);
+                }
+                job.priority = priority;
+                if !due {
+                    job.run_at = now() + chrono::Duration::seconds(1);
+                }
+                if !queued {
+                    job.state = JobState::Blocked;
+                }
+                job
+            })
+            .collect()
+    })
+}
+
+fn shares() -> impl Strategy<Value = FairShare> {
+    prop::collection::vec(1u32..5, TENANTS.len()).prop_map(|weights| {
+        TENANTS
+            .iter()
+            .zip(weights)
+            .fold(FairShare::default(), |shares, (tenant, weight)| {
+                shares.with_weight(*tenant, weight)
+            })
+    })
+}
+
+fn counts(
+    names: &'static [&'static str],
+    max: usize,
+) -> impl Strategy<Value = HashMap<String, usize>> {
+    prop::collection::vec(prop::option::of(0..max), names.len()).prop_map(move |counts| {
+        names
+            .iter()
+            .zip(counts)
+            .filter_map(|(name, count)| Some((name.to_string(), count?)))
+            .collect()
+    })
+}
+
+#[derive(Debug)]
+struct Scenario {
+    jobs: Vec<Job>,
+    max_concurrency: usize,
+    running_by_queue: HashMap<String, usize>,
+    queue_limits: HashMap<String, usize>,
+    running_by_tenant: HashMap<String, usize>,
+    policy: SchedulingPolicy,
+}
+
+impl Scenario {
+    fn running_count(&self) -> usize {
+        self.running_by_queue.values().sum()
+    }
+
+    fn decide(&self, jobs: &[Job]) -> SchedulerDecision {
+        select_jobs(SchedulerInput {
+            queued_jobs: jobs,
+            running_count: self.running_count(),
+            max_concurrency: self.max_concurrency,
+            running_by_queue: &self.running_by_queue,
+            queue_limits: &self.queue_limits,
+            running_by_tenant: &self.running_by_tenant,
+            policy: &self.policy,
+            now: now(),
+        })
+    }
+}
+
+fn fair_share_scenario() -> impl Strategy<Value = Scenario> {
+    (
+        jobs(),
+        0usize..12,
+        counts(&QUEUES, 4),
+        counts(&QUEUES, 6),
+        counts(&TENANTS, 4),
+        shares(),
+    )
+        .prop_map(
+            |(jobs, max_concurrency, running_by_queue, queue_limits, running_by_tenant, shares)| {
+                Scenario {
+                    jobs,
+                    max_concurrency,
+                    running_by_queue,
+                    queue_limits,
+                    running_by_tenant,
+                    policy: SchedulingPolicy::FairShare(shares),
+                }
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn fair_share_is_reproducible(scenario in fair_share_scenario()) {
+        prop_assert_eq!(scenario.decide(&scenario.jobs), scenario.decide(&scenario.jobs));
+    }
+
+    #[test]
+    fn fair_share_ignores_input_order(scenario in fair_share_scenario(), seed in any::<u64>()) {
+        let mut shuffled = scenario.jobs.clone();
+        // Deterministic shuffle keyed by the seed.
+        shuffled.sort_by_key(|job| (job.id.as_u128() as u64).wrapping_mul(seed | 1).rotate_left(17));
+
+        prop_assert_eq!(scenario.decide(&shuffled), scenario.decide(&scenario.jobs));
+    }
+
+    #[test]
+    fn fair_share_respects_limits_and_eligibility(scenario in fair_share_scenario()) {
+        let decision = scenario.decide(&scenario.jobs);
+        let capacity = scenario.max_concurrency.saturating_sub(scenario.running_count());
+
+        prop_assert!(decision.selected_job_ids.len() <= capacity);
+        prop_assert_eq!(decision.remaining_capacity, capacity - decision.selected_job_ids.len());
+
+        let unique: HashSet<_> = decision.selected_job_ids.iter().collect();
+        prop_assert_eq!(unique.len(), decision.selected_job_ids.len());
+
+        let by_id: HashMap<_, _> = scenario.jobs.iter().map(|job| (job.id, job)).collect();
+        let mut per_queue = scenario.running_by_queue.clone();
+
+        for job_id in &decision.selected_job_ids {
+            let job = by_id[job_id];
+            prop_assert_eq!(job.state, JobState::Queued);
+            prop_assert!(job.is_due(now()));
+            *per_queue.entry(job.queue.clone()).or_default() += 1;
+        }
+
+        for (queue, limit) in &scenario.queue_limits {
+            let before = scenario.running_by_queue.get(queue).copied().unwrap_or(0);
+            let after = per_queue.get(queue).copied().unwrap_or(0);
+            prop_assert!(after <= (*limit).max(before), "queue {} over its limit", queue);
+        }
+    }
+
+    /// Without queue limits, a tenant is never ahead of another tenant
+    /// that still has jobs waiting by more than one job's worth of share.
+    #[test]
+    fn fair_share_is_weighted_fair(
+        jobs in jobs(),
+        max_concurrency in 0usize..30,
+        running_by_tenant in counts(&TENANTS, 4),
+        shares in shares(),
+    ) {
+        let scenario = Scenario {
+            jobs,
+            max_concurrency: max_concurrency + running_by_tenant.values().sum::<usize>(),
+            running_by_queue: HashMap::from([("default".to_string(), running_by_tenant.values().sum())]),
+            queue_limits: HashMap::new(),
+            running_by_tenant,
+            policy: SchedulingPolicy::FairShare(shares.clone()),
+        };
+
+        let decision = scenario.decide(&scenario.jobs);
+        let selected: HashSet<_> = decision.selected_job_ids.iter().copied().collect();
+
+        let mut picked: HashMap<&str, usize> = HashMap::new();
+        let mut waiting: HashSet<&str> = HashSet::new();
+
+        for job in scenario.jobs.iter().filter(|job| job.state == JobState::Queued && job.is_due(now())) {
+            if selected.contains(&job.id) {
+                *picked.entry(tenant_key(job)).or_default() += 1;
+            } else {
+                waiting.insert(tenant_key(job));
+            }
+        }
+
+        let allocated = |tenant: &str| {
+            (scenario.running_by_tenant.get(tenant).copied().unwrap_or(0)
+                + picked.get(tenant).copied().unwrap_or(0)) as u64
+        };
+
+        for &a in picked.keys() {
+            for &b in &waiting {
+                // When `a` took its last slot, its share was at most `b`'s.
+                let (wa, wb) = (shares.weight(a) as u64, shares.weight(b) as u64);
+                prop_assert!(
+                    (allocated(a) - 1) * wb <= allocated(b) * wa,
+                    "tenant {:?} got ahead of waiting tenant {:?}", a, b
+                );
+            }
+        }
+    }
+
+    /// With a single tenant, fair share picks what strict priority picks.
+    #[test]
+    fn fair_share_matches_priority_for_one_tenant(
+        jobs in jobs(),
+        max_concurrency in 0usize..12,
+        queue_limits in counts(&QUEUES, 6),
+    ) {
+        let jobs: Vec<Job> = jobs.into_iter().map(|job| job.with_tenant("acme")).collect();
+
+        let decide = |policy: SchedulingPolicy| {
+            Scenario {
+                jobs: jobs.clone(),
+                max_concurrency,
+                running_by_queue: HashMap::new(),
+                queue_limits: queue_limits.clone(),
+                running_by_tenant: HashMap::new(),
+                policy,
+            }
+            .decide(&jobs)
+        };
+
+        prop_assert_eq!(
+            decide(SchedulingPolicy::FairShare(FairShare::default())),
+            decide(SchedulingPolicy::Priority)
+        );
+    }
+}
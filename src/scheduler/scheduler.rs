@@ -1,5 +1,21 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
 use crate::domain::job::Job;
 use crate::domain::state::JobState;
+use crate::scheduler::fair_share::{select_fair_share, FairShare};
+
+/// How `select_jobs` orders eligible jobs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SchedulingPolicy {
+    /// Strict priority DESC, created_at ASC across all jobs.
+    #[default]
+    Priority,
+    /// Weighted fair share of concurrency across tenants; priority order
+    /// within each tenant.
+    FairShare(FairShare),
+}
 
 /// Scheduler input snapshot.
 /// Represents the full scheduling view at a single decision point.
@@ -8,6 +24,15 @@ pub struct SchedulerInput<'a> {
     pub queued_jobs: &'a [Job],
     pub running_count: usize,
     pub max_concurrency: usize,
+    /// Running jobs per queue; queues missing from the map have none.
+    pub running_by_queue: &'a HashMap<String, usize>,
+    /// Concurrency limit per queue; queues missing from the map are only
+    /// bound by `max_concurrency`.
+    pub queue_limits: &'a HashMap<String, usize>,
+    /// Running jobs per tenant, keyed by `tenant_key`; used by fair share.
+    pub running_by_tenant: &'a HashMap<String, usize>,
+    pub policy: &'a SchedulingPolicy,
+    pub now: DateTime<Utc>,
 }
 
 /// Scheduler decision output.
@@ -21,12 +46,16 @@ pub struct SchedulerDecision {
 ///
 /// Rules:
 /// 1. Never exceed max_concurrency.
-/// 2. Only jobs in Queued state are eligible.
-/// 3. Order by:
+/// 2. Never exceed a queue's limit, counting its running jobs.
+/// 3. Only jobs in Queued state are eligible; Blocked jobs never are.
+/// 4. Only jobs whose run_at is at or before `now` are eligible.
+/// 5. Under `Priority`, order by:
 ///    - priority DESC
 ///    - created_at ASC
-/// 4. FIFO within same priority.
-/// 5. If capacity is zero, select nothing.
+/// 6. FIFO within same priority. A job of a full queue is skipped without
+///    holding back jobs of other queues behind it.
+/// 7. Under `FairShare`, see `select_fair_share`.
+/// 8. If capacity is zero, select nothing.
 pub fn select_jobs(input: SchedulerInput) -> SchedulerDecision {
     let available_capacity = input
         .max_concurrency
@@ -39,26 +68,97 @@ pub fn select_jobs(input: SchedulerInput) -> SchedulerDecision {
         };
     }
 
-    let mut candidates: Vec<&Job> = input
+    let candidates: Vec<&Job> = input
         .queued_jobs
         .iter()
-        .filter(|job| job.state == JobState::Queued)
+        .filter(|job| job.state == JobState::Queued && job.is_due(input.now))
         .collect();
 
+    let mut slots = QueueSlots::new(input.running_by_queue, input.queue_limits);
+
+    let selected = match input.policy {
+        SchedulingPolicy::Priority => {
+            select_by_priority(candidates, available_capacity, &mut slots)
+        }
+        SchedulingPolicy::FairShare(shares) => select_fair_share(
+            candidates,
+            available_capacity,
+            &mut slots,
+            input.running_by_tenant,
+            shares,
+        ),
+    };
+
+    SchedulerDecision {
+        remaining_capacity: available_capacity - selected.len(),
+        selected_job_ids: selected,
+    }
+}
+
+/// Key of the job's tenant in `SchedulerInput::running_by_tenant` and
+/// `FairShare` weights; jobs without a tenant share the empty key.
+pub fn tenant_key(job: &Job) -> &str {
+    job.tenant_id.as_deref().unwrap_or("")
+}
+
+fn select_by_priority<'a>(
+    mut candidates: Vec<&'a Job>,
+    capacity: usize,
+    slots: &mut QueueSlots<'a>,
+) -> Vec<uuid::Uuid> {
     candidates.sort_by(|a, b| {
         b.priority
             .cmp(&a.priority)
             .then_with(|| a.created_at.cmp(&b.created_at))
     });
 
-    let selected: Vec<uuid::Uuid> = candidates
-        .into_iter()
-        .take(available_capacity)
-        .map(|job| job.id)
-        .collect();
+    let mut selected: Vec<uuid::Uuid> = Vec::new();
 
-    SchedulerDecision {
-        remaining_capacity: available_capacity - selected.len(),
-        selected_job_ids: selected,
+    for job in candidates {
+        if selected.len() == capacity {
+            break;
+        }
+
+        if slots.take(&job.queue) {
+            selected.push(job.id);
+        }
+    }
+
+    selected
+}
+
+/// Remaining room per queue while a decision is being built.
+pub(crate) struct QueueSlots<'a> {
+    running_by_queue: &'a HashMap<String, usize>,
+    queue_limits: &'a HashMap<String, usize>,
+    counts: HashMap<&'a str, usize>,
+}
+
+impl<'a> QueueSlots<'a> {
+    fn new(
+        running_by_queue: &'a HashMap<String, usize>,
+        queue_limits: &'a HashMap<String, usize>,
+    ) -> Self {
+        Self {
+            running_by_queue,
+            queue_limits,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Takes a slot in `queue`; false if the queue is at its limit.
+    pub(crate) fn take(&mut self, queue: &'a str) -> bool {
+        let running_by_queue = self.running_by_queue;
+        let count = self
+            .counts
+            .entry(queue)
+            .or_insert_with(|| running_by_queue.get(queue).copied().unwrap_or(0));
+
+        if self.queue_limits.get(queue).is_some_and(|limit| *count >= *limit) {
+            return false;
+        }
+
+        *count += 1;
+        true
     }
 }
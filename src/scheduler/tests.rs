@@ -1,21 +1,94 @@
-use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeZone, Utc};
 use uuid::Uuid;
 
 use crate::domain::job::Job;
 use crate::domain::state::JobState;
-use crate::scheduler::{select_jobs, SchedulerInput, SchedulerDecision};
+use crate::scheduler::{select_jobs, FairShare, SchedulerDecision, SchedulerInput, SchedulingPolicy};
 
 fn job(id: u8, priority: i32, created_at: i64) -> Job {
     Job {
-        id: Uuid::from_u128(id as u128),
-        payload: serde_json::json!({}),
         priority,
-        state: JobState::Queued,
-        attempt: 0,
-        max_attempts: 3,
-        failure: None,
-        created_at: Utc.timestamp_opt(created_at, 0).unwrap(),
-        updated_at: Utc.timestamp_opt(created_at, 0).unwrap(),
+        ..Job::new(
+            Uuid::from_u128(id as u128),
+            serde_json::json!({}),
+            Utc.timestamp_opt(created_at, 0).unwrap(),
+        )
+    }
+}
+
+fn now() -> DateTime<Utc> {
+    Utc.timestamp_opt(1_000, 0).unwrap()
+}
+
+fn counts(entries: &[(&str, usize)]) -> HashMap<String, usize> {
+    entries
+        .iter()
+        .map(|(key, count)| (key.to_string(), *count))
+        .collect()
+}
+
+/// Owns what `SchedulerInput` borrows. Starts with nothing running, no
+/// queue limits and strict priority at `now()`.
+struct Input {
+    running_count: usize,
+    max_concurrency: usize,
+    running_by_queue: HashMap<String, usize>,
+    queue_limits: HashMap<String, usize>,
+    running_by_tenant: HashMap<String, usize>,
+    policy: SchedulingPolicy,
+    now: DateTime<Utc>,
+}
+
+fn input(max_concurrency: usize) -> Input {
+    Input {
+        running_count: 0,
+        max_concurrency,
+        running_by_queue: HashMap::new(),
+        queue_limits: HashMap::new(),
+        running_by_tenant: HashMap::new(),
+        policy: SchedulingPolicy::Priority,
+        now: now(),
+    }
+}
+
+impl Input {
+    fn running(self, running_count: usize) -> Self {
+        Self { running_count, ..self }
+    }
+
+    fn running_by_queue(self, entries: &[(&str, usize)]) -> Self {
+        Self { running_by_queue: counts(entries), ..self }
+    }
+
+    fn queue_limits(self, entries: &[(&str, usize)]) -> Self {
+        Self { queue_limits: counts(entries), ..self }
+    }
+
+    fn running_by_tenant(self, entries: &[(&str, usize)]) -> Self {
+        Self { running_by_tenant: counts(entries), ..self }
+    }
+
+    fn policy(self, policy: SchedulingPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    fn at(self, now: DateTime<Utc>) -> Self {
+        Self { now, ..self }
+    }
+
+    fn decide(&self, jobs: &[Job]) -> SchedulerDecision {
+        select_jobs(SchedulerInput {
+            queued_jobs: jobs,
+            running_count: self.running_count,
+            max_concurrency: self.max_concurrency,
+            running_by_queue: &self.running_by_queue,
+            queue_limits: &self.queue_limits,
+            running_by_tenant: &self.running_by_tenant,
+            policy: &self.policy,
+            now: self.now,
+        })
     }
 }
 
@@ -23,11 +96,7 @@ fn job(id: u8, priority: i32, created_at: i64) -> Job {
 fn respects_concurrency_limit() {
     let jobs = vec![job(1, 0, 1), job(2, 0, 2), job(3, 0, 3)];
 
-    let decision = select_jobs(SchedulerInput {
-        queued_jobs: &jobs,
-        running_count: 1,
-        max_concurrency: 2,
-    });
+    let decision = input(2).running(1).decide(&jobs);
 
     assert_eq!(decision.selected_job_ids.len(), 1);
     assert_eq!(decision.remaining_capacity, 0);
@@ -42,11 +111,7 @@ fn orders_by_priority_then_fifo() {
         job(4, 1, 1),
     ];
 
-    let decision = select_jobs(SchedulerInput {
-        queued_jobs: &jobs,
-        running_count: 0,
-        max_concurrency: 10,
-    });
+    let decision = input(10).decide(&jobs);
 
     let expected = vec![
         Uuid::from_u128(3),
@@ -62,11 +127,7 @@ fn orders_by_priority_then_fifo() {
 fn selects_nothing_when_saturated() {
     let jobs = vec![job(1, 0, 1)];
 
-    let decision = select_jobs(SchedulerInput {
-        queued_jobs: &jobs,
-        running_count: 5,
-        max_concurrency: 5,
-    });
+    let decision = input(5).running(5).decide(&jobs);
 
     assert_eq!(
         decision,
@@ -76,3 +137,132 @@ fn selects_nothing_when_saturated() {
         }
     );
 }
+
+#[test]
+fn skips_jobs_not_yet_due() {
+    let mut delayed = job(1, 5, 1);
+    delayed.run_at = Utc.timestamp_opt(2_000, 0).unwrap();
+
+    let jobs = vec![delayed, job(2, 0, 2)];
+
+    let decision = input(10).decide(&jobs);
+
+    assert_eq!(decision.selected_job_ids, vec![Uuid::from_u128(2)]);
+
+    let decision = input(10).at(Utc.timestamp_opt(2_000, 0).unwrap()).decide(&jobs);
+
+    assert_eq!(
+        decision.selected_job_ids,
+        vec![Uuid::from_u128(1), Uuid::from_u128(2)]
+    );
+}
+
+#[test]
+fn never_selects_blocked_jobs() {
+    let mut blocked = job(1, 10, 1);
+    blocked.state = JobState::Blocked;
+
+    let jobs = vec![blocked, job(2, 0, 2)];
+
+    let decision = input(10).decide(&jobs);
+
+    assert_eq!(decision.selected_job_ids, vec![Uuid::from_u128(2)]);
+}
+
+fn queued_in(queue: &str, id: u8, priority: i32, created_at: i64) -> Job {
+    job(id, priority, created_at).with_queue(queue)
+}
+
+#[test]
+fn respects_queue_limits() {
+    let jobs = vec![
+        queued_in("bulk", 1, 5, 1),
+        queued_in("bulk", 2, 5, 2),
+        queued_in("bulk", 3, 5, 3),
+        queued_in("urgent", 4, 0, 4),
+    ];
+
+    let decision = input(10)
+        .running(1)
+        .running_by_queue(&[("bulk", 1)])
+        .queue_limits(&[("bulk", 2)])
+        .decide(&jobs);
+
+    // One bulk job already runs, so only one more fits; the full queue does
+    // not hold back the lower-priority urgent job.
+    assert_eq!(
+        decision.selected_job_ids,
+        vec![Uuid::from_u128(1), Uuid::from_u128(4)]
+    );
+    assert_eq!(decision.remaining_capacity, 7);
+}
+
+#[test]
+fn global_limit_still_applies_across_queues() {
+    let jobs = vec![
+        queued_in("a", 1, 0, 1),
+        queued_in("b", 2, 0, 2),
+        queued_in("c", 3, 0, 3),
+    ];
+
+    let decision = input(2).queue_limits(&[("a", 5), ("b", 5), ("c", 5)]).decide(&jobs);
+
+    assert_eq!(
+        decision.selected_job_ids,
+        vec![Uuid::from_u128(1), Uuid::from_u128(2)]
+    );
+}
+
+#[test]
+fn saturated_queue_selects_nothing_from_it() {
+    let jobs = vec![queued_in("bulk", 1, 0, 1), queued_in("bulk", 2, 0, 2)];
+
+    let decision = input(10)
+        .running(3)
+        .running_by_queue(&[("bulk", 3)])
+        .queue_limits(&[("bulk", 3)])
+        .decide(&jobs);
+
+    assert!(decision.selected_job_ids.is_empty());
+    assert_eq!(decision.remaining_capacity, 7);
+}
+
+fn owned_by(tenant: &str, id: u8, priority: i32, created_at: i64) -> Job {
+    job(id, priority, created_at).with_tenant(tenant)
+}
+
+#[test]
+fn fair_share_interleaves_tenants_by_weight() {
+    // "big" floods the queue with older, higher-priority jobs.
+    let mut jobs: Vec<Job> = (1..=6).map(|id| owned_by("big", id, 5, id as i64)).collect();
+    jobs.extend((7..=9).map(|id| owned_by("small", id, 0, id as i64)));
+
+    let policy = SchedulingPolicy::FairShare(FairShare::default().with_weight("big", 2));
+
+    let decision = input(6).policy(policy).decide(&jobs);
+
+    let ids: Vec<_> = [1, 7, 2, 3, 8, 4].into_iter().map(Uuid::from_u128).collect();
+    assert_eq!(decision.selected_job_ids, ids);
+}
+
+#[test]
+fn fair_share_counts_running_jobs() {
+    let jobs = vec![
+        owned_by("a", 1, 0, 1),
+        owned_by("a", 2, 0, 2),
+        owned_by("b", 3, 0, 3),
+        owned_by("b", 4, 0, 4),
+    ];
+
+    let decision = input(4)
+        .running(2)
+        .running_by_tenant(&[("a", 2)])
+        .policy(SchedulingPolicy::FairShare(FairShare::default()))
+        .decide(&jobs);
+
+    // "a" already runs two, so both free slots go to "b".
+    assert_eq!(
+        decision.selected_job_ids,
+        vec![Uuid::from_u128(3), Uuid::from_u128(4)]
+    );
+}
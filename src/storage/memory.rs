@@ -0,0 +1,484 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::dependencies::BlockedJob;
+use crate::domain::failure::Failure;
+use crate::domain::job::Job;
+use crate::domain::state::JobState;
+use crate::logs::{JobLogEntry, JobLogLine};
+use crate::recovery::{ForcedFailure, RecoveryRun};
+use crate::retry::RetryDecision;
+use crate::storage::repository::{
+    FailGuard, Insertion, JobFilter, JobLogRepository, JobRepository, RepositoryError,
+};
+
+type Hook = Box<dyn FnOnce(&mut [Job]) + Send>;
+
+/// In-memory `JobRepository` and `JobLogRepository` for tests, with the
+/// compare-and-swap semantics of the Postgres one. Idempotency and unique
+/// keys are not enforced.
+#[derive(Default)]
+pub(crate) struct MemoryJobs {
+    jobs: Mutex<Vec<Job>>,
+    /// (job id, event reason), oldest first.
+    events: Mutex<Vec<(Uuid, String)>>,
+    before_claim: Mutex<Option<Hook>>,
+    before_state_updates: Mutex<Vec<Hook>>,
+    logs: Mutex<Vec<(Uuid, JobLogLine)>>,
+}
+
+impl MemoryJobs {
+    pub(crate) fn with_jobs(jobs: impl IntoIterator<Item = Job>) -> Self {
+        let repository = Self::default();
+        repository.jobs.lock().unwrap().extend(jobs);
+        repository
+    }
+
+    /// The stored job; panics if there is none.
+    pub(crate) fn job(&self, job_id: Uuid) -> Job {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|job| job.id == job_id)
+            .cloned()
+            .expect("job exists")
+    }
+
+    /// Reasons of the events recorded for the job, oldest first.
+    pub(crate) fn event_reasons(&self, job_id: Uuid) -> Vec<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| *id == job_id)
+            .map(|(_, reason)| reason.clone())
+            .collect()
+    }
+
+    /// Runs `hook` over the stored jobs at the start of the next
+    /// `claim_jobs`, e.g. to play a competing orchestrator.
+    pub(crate) fn before_next_claim(&self, hook: impl FnOnce(&mut [Job]) + Send + 'static) {
+        *self.before_claim.lock().unwrap() = Some(Box::new(hook));
+    }
+
+    /// Queues `hook` to run over the stored jobs at the start of an
+    /// upcoming `update_job_state`, one hook per call, e.g. to play a
+    /// concurrent heartbeat or edit.
+    pub(crate) fn before_next_state_update(&self, hook: impl FnOnce(&mut [Job]) + Send + 'static) {
+        self.before_state_updates.lock().unwrap().push(Box::new(hook));
+    }
+
+    fn find(&self, filter: impl Fn(&Job) -> bool) -> Vec<Job> {
+        self.jobs.lock().unwrap().iter().filter(|job| filter(job)).cloned().collect()
+    }
+
+    fn record(&self, job_id: Uuid, reason: impl Into<String>) {
+        self.events.lock().unwrap().push((job_id, reason.into()));
+    }
+
+    /// Applies `update` if the job is in `state` at `version`, bumping the
+    /// version; otherwise explains the lost update like the Postgres one.
+    fn swap(
+        &self,
+        job_id: Uuid,
+        state: JobState,
+        version: i64,
+        update: impl FnOnce(&mut Job),
+    ) -> Result<i64, RepositoryError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.id == job_id)
+            .ok_or(RepositoryError::NotFound(job_id))?;
+
+        if job.state != state {
+            return Err(RepositoryError::StaleState { job_id, expected: state, actual: job.state });
+        }
+        if job.version != version {
+            return Err(RepositoryError::VersionConflict {
+                job_id,
+                expected: version,
+                actual: job.version,
+            });
+        }
+
+        update(job);
+        job.version += 1;
+        Ok(job.version)
+    }
+
+    /// Applies `update` if the job is Running under `worker_id`'s lease,
+    /// bumping the version.
+    fn swap_leased(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        update: impl FnOnce(&mut Job),
+    ) -> Result<i64, RepositoryError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.id == job_id)
+            .ok_or(RepositoryError::NotFound(job_id))?;
+
+        if job.state != JobState::Running {
+            return Err(RepositoryError::StaleState {
+                job_id,
+                expected: JobState::Running,
+                actual: job.state,
+            });
+        }
+        if job.lease_owner.as_deref() != Some(worker_id) {
+            return Err(RepositoryError::LeaseLost { job_id, worker_id: worker_id.to_string() });
+        }
+
+        update(job);
+        job.version += 1;
+        Ok(job.version)
+    }
+
+    fn fail_running(
+        &self,
+        job_id: Uuid,
+        guard: FailGuard<'_>,
+        failure: &Failure,
+        decision: &RetryDecision,
+    ) -> Result<i64, RepositoryError> {
+        let update = |job: &mut Job| {
+            job.state = JobState::Failed;
+            job.attempt += 1;
+            job.failure = Some(failure.clone());
+            job.lease_owner = None;
+            job.lease_expires_at = None;
+
+            if let RetryDecision::Retry { next_run_at, .. } = decision {
+                job.state = JobState::Queued;
+                job.run_at = *next_run_at;
+            }
+        };
+
+        let version = match guard {
+            FailGuard::Lease(worker_id) => self.swap_leased(job_id, worker_id, update)?,
+            FailGuard::Version(version) => self.swap(job_id, JobState::Running, version, update)?,
+        };
+
+        self.record(job_id, decision.to_string());
+        Ok(version)
+    }
+}
+
+#[async_trait::async_trait]
+impl JobRepository for MemoryJobs {
+    async fn fetch_queued_jobs(&self, now: DateTime<Utc>) -> Result<Vec<Job>, RepositoryError> {
+        let mut jobs = self.find(|job| job.state == JobState::Queued && job.is_due(now));
+        jobs.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.created_at.cmp(&b.created_at)));
+        Ok(jobs)
+    }
+
+    async fn fetch_running_jobs(&self) -> Result<Vec<Job>, RepositoryError> {
+        Ok(self.find(|job| job.state == JobState::Running))
+    }
+
+    async fn fetch_blocked_jobs(&self) -> Result<Vec<BlockedJob>, RepositoryError> {
+        let jobs = self.find(|_| true);
+
+        Ok(jobs
+            .iter()
+            .filter(|job| job.state == JobState::Blocked)
+            .map(|job| BlockedJob {
+                job: job.clone(),
+                parent_states: jobs
+                    .iter()
+                    .filter(|parent| job.depends_on.contains(&parent.id))
+                    .map(|parent| parent.state)
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn fetch_job(&self, job_id: Uuid) -> Result<Job, RepositoryError> {
+        self.find(|job| job.id == job_id)
+            .pop()
+            .ok_or(RepositoryError::NotFound(job_id))
+    }
+
+    async fn list_jobs(&self, filter: &JobFilter) -> Result<Vec<Job>, RepositoryError> {
+        let mut jobs = self.find(|job| {
+            filter.state.is_none_or(|state| job.state == state)
+                && filter.job_type.as_ref().is_none_or(|job_type| job.job_type == *job_type)
+                && filter.queue.as_ref().is_none_or(|queue| job.queue == *queue)
+        });
+        jobs.reverse();
+
+        Ok(jobs
+            .into_iter()
+            .skip(filter.offset as usize)
+            .take(filter.limit as usize)
+            .collect())
+    }
+
+    async fn claim_jobs(
+        &self,
+        jobs: &[Job],
+        worker_id: &str,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<Vec<Job>, RepositoryError> {
+        let hook = self.before_claim.lock().unwrap().take();
+        if let Some(hook) = hook {
+            hook(&mut self.jobs.lock().unwrap());
+        }
+
+        let mut claimed = Vec::new();
+
+        for job in jobs {
+            let swapped = self.swap(job.id, JobState::Queued, job.version, |job| {
+                job.state = JobState::Running;
+                job.lease_owner = Some(worker_id.to_string());
+                job.lease_expires_at = Some(lease_expires_at);
+                job.failure = None;
+            });
+
+            if swapped.is_ok() {
+                self.record(job.id, format!("claimed by {worker_id}"));
+                claimed.push(self.job(job.id));
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    async fn renew_lease(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<i64, RepositoryError> {
+        self.swap_leased(job_id, worker_id, |job| {
+            job.lease_expires_at = Some(lease_expires_at);
+        })
+    }
+
+    async fn save_progress(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        progress: Option<u8>,
+        checkpoint: Option<&[u8]>,
+    ) -> Result<i64, RepositoryError> {
+        self.swap_leased(job_id, worker_id, |job| {
+            job.progress = progress.or(job.progress);
+            job.checkpoint = checkpoint.map(<[u8]>::to_vec).or(job.checkpoint.take());
+        })
+    }
+
+    async fn insert_job(&self, job: &Job) -> Result<Insertion, RepositoryError> {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        if jobs.iter().any(|existing| existing.id == job.id) {
+            return Err(RepositoryError::AlreadyExists);
+        }
+        if !job.depends_on.iter().all(|parent| jobs.iter().any(|existing| existing.id == *parent)) {
+            return Err(RepositoryError::UnknownDependency);
+        }
+
+        jobs.push(job.clone());
+        drop(jobs);
+
+        self.record(job.id, "job created");
+        Ok(Insertion::Created)
+    }
+
+    async fn update_job_state(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        from: JobState,
+        to: JobState,
+        failure: Option<&Failure>,
+    ) -> Result<i64, RepositoryError> {
+        let hook = {
+            let mut hooks = self.before_state_updates.lock().unwrap();
+            (!hooks.is_empty()).then(|| hooks.remove(0))
+        };
+        if let Some(hook) = hook {
+            hook(&mut self.jobs.lock().unwrap());
+        }
+
+        from.transition(to, failure)?;
+
+        let version = self.swap(job_id, from, version, |job| {
+            job.state = to;
+            job.lease_owner = None;
+            job.lease_expires_at = None;
+            job.failure = failure.cloned();
+
+            if from == JobState::Running && to == JobState::Failed {
+                job.attempt += 1;
+            }
+        })?;
+
+        self.record(job_id, "state transition");
+        Ok(version)
+    }
+
+    async fn requeue_job(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        run_at: DateTime<Utc>,
+    ) -> Result<i64, RepositoryError> {
+        JobState::Failed.transition(JobState::Queued, None)?;
+
+        let version = self.swap(job_id, JobState::Failed, version, |job| {
+            job.state = JobState::Queued;
+            job.attempt = 0;
+            job.run_at = run_at;
+            job.failure = None;
+        })?;
+
+        self.record(job_id, "requeued");
+        Ok(version)
+    }
+
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        result: Option<&serde_json::Value>,
+    ) -> Result<i64, RepositoryError> {
+        let version = self.swap_leased(job_id, worker_id, |job| {
+            job.state = JobState::Succeeded;
+            job.lease_owner = None;
+            job.lease_expires_at = None;
+            job.result = result.cloned();
+        })?;
+
+        self.record(job_id, "job succeeded");
+        Ok(version)
+    }
+
+    async fn release_job(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        reason: &str,
+    ) -> Result<i64, RepositoryError> {
+        let version = self.swap_leased(job_id, worker_id, |job| {
+            job.state = JobState::Queued;
+            job.lease_owner = None;
+            job.lease_expires_at = None;
+        })?;
+
+        self.record(job_id, reason);
+        Ok(version)
+    }
+
+    async fn fail_job(
+        &self,
+        job_id: Uuid,
+        guard: FailGuard<'_>,
+        failure: &Failure,
+        decision: &RetryDecision,
+    ) -> Result<i64, RepositoryError> {
+        self.fail_running(job_id, guard, failure, decision)
+    }
+
+    async fn recover_jobs(
+        &self,
+        worker_id: &str,
+        started_at: DateTime<Utc>,
+        failures: &[ForcedFailure],
+        skipped_job_ids: &[Uuid],
+    ) -> Result<RecoveryRun, RepositoryError> {
+        let mut recovered_job_ids = Vec::new();
+        let mut skipped_job_ids = skipped_job_ids.to_vec();
+
+        for forced in failures {
+            let guard = FailGuard::Version(forced.version);
+
+            match self.fail_running(forced.job_id, guard, &forced.failure, &forced.decision) {
+                Ok(_) => recovered_job_ids.push(forced.job_id),
+                Err(_) => skipped_job_ids.push(forced.job_id),
+            }
+        }
+
+        Ok(RecoveryRun {
+            id: Uuid::new_v4(),
+            worker_id: worker_id.to_string(),
+            recovered_job_ids,
+            skipped_job_ids,
+            started_at,
+            finished_at: Utc::now(),
+        })
+    }
+
+    async fn update_job_priority(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        priority: i32,
+    ) -> Result<i64, RepositoryError> {
+        let state = self.fetch_job(job_id).await?.state;
+        let version = self.swap(job_id, state, version, |job| job.priority = priority)?;
+
+        self.record(job_id, format!("priority changed to {priority}"));
+        Ok(version)
+    }
+
+    async fn update_job_payload(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        payload: &serde_json::Value,
+    ) -> Result<i64, RepositoryError> {
+        let state = self.fetch_job(job_id).await?.state;
+        let version = self.swap(job_id, state, version, |job| job.payload = payload.clone())?;
+
+        self.record(job_id, "payload replaced");
+        Ok(version)
+    }
+}
+
+#[async_trait::async_trait]
+impl JobLogRepository for MemoryJobs {
+    async fn append_job_logs(&self, entries: &[JobLogEntry]) -> Result<(), RepositoryError> {
+        let mut logs = self.logs.lock().unwrap();
+
+        for entry in entries {
+            let offset = logs.iter().filter(|(job_id, _)| *job_id == entry.job_id).count();
+            logs.push((
+                entry.job_id,
+                JobLogLine {
+                    offset: offset as i64,
+                    attempt: entry.attempt,
+                    level: entry.level,
+                    message: entry.message.clone(),
+                    logged_at: entry.logged_at,
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_job_logs(
+        &self,
+        job_id: Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<JobLogLine>, RepositoryError> {
+        Ok(self
+            .logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _)| *id == job_id)
+            .map(|(_, line)| line.clone())
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+}
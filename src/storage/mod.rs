@@ -1,4 +1,8 @@
 pub mod repository;
 pub mod postgres;
+#[cfg(test)]
+pub(crate) mod memory;
+#[cfg(test)]
+mod tests;
 
 pub use postgres::PostgresJobRepository;
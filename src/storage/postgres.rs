@@ -1,10 +1,36 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Postgres, Transaction, Row};
 use uuid::Uuid;
 
+use crate::dependencies::{BlockedJob, DependencyFailurePolicy};
 use crate::domain::failure::{Failure, FailureKind};
-use crate::domain::job::Job;
+use crate::domain::job::{Debounce, Job};
 use crate::domain::state::JobState;
-use crate::storage::repository::{JobRepository, RepositoryError};
+use crate::recovery::{ForcedFailure, RecoveryRun};
+use crate::recurring::{MissedFirePolicy, RecurringJob};
+use crate::retry::{FailureClassification, RetryDecision};
+use crate::logs::{JobLogEntry, JobLogLine, LogLevel};
+use crate::storage::repository::{
+    FailGuard, Insertion, JobFilter, JobLogRepository, JobRepository, RecurringJobRepository, RepositoryError,
+};
+
+const JOB_COLUMNS: &str = r#"
+    id, job_type, queue, tenant_id, payload, priority, state, version, attempt, max_attempts,
+    run_at, timeout_ms, failure_classification, failure_type, failure_reason, result,
+    progress, checkpoint,
+    dependency_failure_policy,
+    ARRAY(
+        SELECT d.depends_on FROM job_dependencies d WHERE d.job_id = jobs.id
+    ) AS depends_on,
+    lease_owner, lease_expires_at,
+    idempotency_key, idempotency_expires_at, unique_key,
+    recurring_job_id, fire_time, created_at, updated_at
+"#;
+
+/// Attempts `insert_job` makes before giving up on a contended unique key.
+const MAX_INSERT_ATTEMPTS: usize = 3;
 
 pub struct PostgresJobRepository {
     pool: PgPool,
@@ -14,131 +40,1186 @@ impl PostgresJobRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
+
+    /// One attempt of `insert_job`; `None` if a concurrent submitter took
+    /// the unique key first.
+    async fn try_insert_job(&self, job: &Job) -> Result<Option<Insertion>, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(key) = &job.idempotency_key {
+            // Expired keys are released so this job can take them over.
+            sqlx::query(
+                r#"
+                UPDATE jobs
+                SET idempotency_key = NULL, idempotency_expires_at = NULL
+                WHERE COALESCE(tenant_id, '') = COALESCE($1, '')
+                  AND idempotency_key = $2
+                  AND idempotency_expires_at <= $3
+                "#
+            )
+            .bind(job.tenant_id.as_deref())
+            .bind(key)
+            .bind(job.created_at)
+            .execute(&mut *tx)
+            .await?;
+
+            if let Some(existing) = idempotent_job(&mut tx, job).await? {
+                tx.commit().await?;
+                return Ok(Some(Insertion::Existing(Box::new(existing))));
+            }
+        }
+
+        if let Some(key) = &job.unique_key {
+            if let Some(holder) = live_unique_job(&mut tx, job.tenant_id.as_deref(), key).await? {
+                let insertion = debounce(&mut tx, holder, job).await?;
+                tx.commit().await?;
+                return Ok(Some(insertion));
+            }
+        }
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO jobs (
+                id, job_type, queue, tenant_id, payload, priority, state, attempt, max_attempts,
+                run_at, timeout_ms, failure_classification, dependency_failure_policy,
+                idempotency_key, idempotency_expires_at, unique_key,
+                recurring_job_id, fire_time, created_at, updated_at
+            )
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20)
+            ON CONFLICT (COALESCE(tenant_id, ''), idempotency_key)
+                WHERE idempotency_key IS NOT NULL
+                DO NOTHING
+            RETURNING id
+            "#
+        )
+        .bind(job.id)
+        .bind(&job.job_type)
+        .bind(&job.queue)
+        .bind(job.tenant_id.as_deref())
+        .bind(&job.payload)
+        .bind(job.priority)
+        .bind(state_to_str(job.state))
+        .bind(job.attempt as i32)
+        .bind(job.max_attempts as i32)
+        .bind(job.run_at)
+        .bind(job.timeout.map(|timeout| timeout.as_millis() as i64))
+        .bind(job.failure_classification.as_ref().map(sqlx::types::Json))
+        .bind(dependency_policy_to_str(job.dependency_failure_policy))
+        .bind(job.idempotency_key.as_deref())
+        .bind(job.idempotency_expires_at)
+        .bind(job.unique_key.as_deref())
+        .bind(job.recurring_job_id)
+        .bind(job.fire_time)
+        .bind(job.created_at)
+        .bind(job.updated_at)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_unique_violation);
+
+        let inserted = match inserted {
+            Err(RepositoryError::UniqueKeyTaken) => return Ok(None),
+            inserted => inserted?,
+        };
+
+        if inserted.is_none() {
+            // Lost a race for the idempotency key.
+            let existing = idempotent_job(&mut tx, job)
+                .await?
+                .ok_or(RepositoryError::AlreadyExists)?;
+            tx.commit().await?;
+            return Ok(Some(Insertion::Existing(Box::new(existing))));
+        }
+
+        if !job.depends_on.is_empty() {
+            sqlx::query(
+                r#"
+                INSERT INTO job_dependencies (job_id, depends_on)
+                SELECT $1, parent FROM UNNEST($2::uuid[]) AS parent
+                "#
+            )
+            .bind(job.id)
+            .bind(&job.depends_on)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_dependency_violation)?;
+        }
+
+        insert_event(&mut tx, job.id, job.state, job.state, "job created").await?;
+        tx.commit().await?;
+        Ok(Some(Insertion::Created))
+    }
 }
 
 #[async_trait::async_trait]
 impl JobRepository for PostgresJobRepository {
-    async fn fetch_queued_jobs(&self) -> Result<Vec<Job>, RepositoryError> {
-        let rows = sqlx::query(
+    async fn fetch_queued_jobs(&self, now: DateTime<Utc>) -> Result<Vec<Job>, RepositoryError> {
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT
-                id, payload, priority, state, attempt, max_attempts,
-                failure_type, failure_reason, created_at, updated_at
+            SELECT {JOB_COLUMNS}
             FROM jobs
-            WHERE state = 'queued'
+            WHERE state = 'queued' AND run_at <= $1
             ORDER BY priority DESC, created_at ASC
             "#
-        )
+        ))
+        .bind(now)
         .fetch_all(&self.pool)
         .await?;
 
-        rows.into_iter().map(row_to_job).collect()
+        rows.iter().map(row_to_job).collect()
     }
 
     async fn fetch_running_jobs(&self) -> Result<Vec<Job>, RepositoryError> {
-        let rows = sqlx::query(
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT
-                id, payload, priority, state, attempt, max_attempts,
-                failure_type, failure_reason, created_at, updated_at
+            SELECT {JOB_COLUMNS}
             FROM jobs
             WHERE state = 'running'
             "#
-        )
+        ))
         .fetch_all(&self.pool)
         .await?;
 
-        rows.into_iter().map(row_to_job).collect()
+        rows.iter().map(row_to_job).collect()
     }
 
-    async fn insert_job(&self, job: &Job) -> Result<(), RepositoryError> {
-        let mut tx = self.pool.begin().await?;
+    async fn fetch_blocked_jobs(&self) -> Result<Vec<BlockedJob>, RepositoryError> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT
+                {JOB_COLUMNS},
+                ARRAY(
+                    SELECT p.state
+                    FROM job_dependencies d
+                    JOIN jobs p ON p.id = d.depends_on
+                    WHERE d.job_id = jobs.id
+                ) AS parent_states
+            FROM jobs
+            WHERE state = 'blocked'
+            ORDER BY priority DESC, created_at ASC
+            "#
+        ))
+        .fetch_all(&self.pool)
+        .await?;
 
-        sqlx::query(
+        rows.into_iter()
+            .map(|row| {
+                let parent_states = row
+                    .try_get::<Vec<String>, _>("parent_states")?
+                    .into_iter()
+                    .map(str_to_state)
+                    .collect();
+
+                Ok(BlockedJob { job: row_to_job(&row)?, parent_states })
+            })
+            .collect()
+    }
+
+    async fn fetch_job(&self, job_id: Uuid) -> Result<Job, RepositoryError> {
+        let row = sqlx::query(&format!(
             r#"
-            INSERT INTO jobs (
-                id, payload, priority, state, attempt, max_attempts,
-                created_at, updated_at
+            SELECT {JOB_COLUMNS}
+            FROM jobs
+            WHERE id = $1
+            "#
+        ))
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => row_to_job(&row),
+            None => Err(RepositoryError::NotFound(job_id)),
+        }
+    }
+
+    async fn list_jobs(&self, filter: &JobFilter) -> Result<Vec<Job>, RepositoryError> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {JOB_COLUMNS}
+            FROM jobs
+            WHERE ($1::text IS NULL OR state = $1)
+              AND ($2::text IS NULL OR job_type = $2)
+              AND ($3::text IS NULL OR queue = $3)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4 OFFSET $5
+            "#
+        ))
+        .bind(filter.state.map(state_to_str))
+        .bind(filter.job_type.as_deref())
+        .bind(filter.queue.as_deref())
+        .bind(filter.limit)
+        .bind(filter.offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_job).collect()
+    }
+
+    async fn claim_jobs(
+        &self,
+        jobs: &[Job],
+        worker_id: &str,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<Vec<Job>, RepositoryError> {
+        if jobs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<Uuid> = jobs.iter().map(|job| job.id).collect();
+        let versions: Vec<i64> = jobs.iter().map(|job| job.version).collect();
+
+        let rows = sqlx::query(&format!(
+            r#"
+            WITH claimable AS (
+                SELECT id AS claim_id
+                FROM jobs
+                WHERE state = 'queued'
+                  AND (id, version) IN (SELECT * FROM UNNEST($1::uuid[], $2::bigint[]))
+                FOR UPDATE SKIP LOCKED
+            ),
+            claimed AS (
+                UPDATE jobs
+                SET
+                    state = 'running',
+                    version = version + 1,
+                    lease_owner = $3,
+                    lease_expires_at = $4,
+                    failure_type = NULL,
+                    failure_reason = NULL,
+                    updated_at = now()
+                FROM claimable
+                WHERE jobs.id = claimable.claim_id
+                RETURNING {JOB_COLUMNS}
+            ),
+            events AS (
+                INSERT INTO job_events (job_id, from_state, to_state, event_reason)
+                SELECT id, 'queued', 'running', 'claimed by ' || $3
+                FROM claimed
             )
-            VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+            SELECT * FROM claimed
+            "#
+        ))
+        .bind(&ids)
+        .bind(&versions)
+        .bind(worker_id)
+        .bind(lease_expires_at)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut claimed = rows.iter().map(row_to_job).collect::<Result<Vec<_>, _>>()?;
+        claimed.sort_by_key(|job| ids.iter().position(|id| *id == job.id));
+
+        Ok(claimed)
+    }
+
+    async fn renew_lease(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<i64, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET
+                version = version + 1,
+                lease_expires_at = $1,
+                updated_at = now()
+            WHERE id = $2 AND state = 'running' AND lease_owner = $3
+            RETURNING version
             "#
         )
-        .bind(job.id)
-        .bind(&job.payload)
-        .bind(job.priority)
-        .bind(state_to_str(job.state))
-        .bind(job.attempt as i32)
-        .bind(job.max_attempts as i32)
-        .bind(job.created_at)
-        .bind(job.updated_at)
-        .execute(&mut *tx)
+        .bind(lease_expires_at)
+        .bind(job_id)
+        .bind(worker_id)
+        .fetch_optional(&mut *tx)
         .await?;
 
-        insert_event(&mut tx, job.id, job.state, job.state, "job created").await?;
+        let Some(row) = updated else {
+            return Err(lost_lease(&mut tx, job_id, worker_id).await);
+        };
+
         tx.commit().await?;
-        Ok(())
+        Ok(row.try_get("version")?)
+    }
+
+    async fn save_progress(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        progress: Option<u8>,
+        checkpoint: Option<&[u8]>,
+    ) -> Result<i64, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET
+                version = version + 1,
+                progress = COALESCE($1, progress),
+                checkpoint = COALESCE($2, checkpoint),
+                updated_at = now()
+            WHERE id = $3 AND state = 'running' AND lease_owner = $4
+            RETURNING version
+            "#
+        )
+        .bind(progress.map(i16::from))
+        .bind(checkpoint)
+        .bind(job_id)
+        .bind(worker_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = updated else {
+            return Err(lost_lease(&mut tx, job_id, worker_id).await);
+        };
+
+        tx.commit().await?;
+        Ok(row.try_get("version")?)
+    }
+
+    async fn insert_job(&self, job: &Job) -> Result<Insertion, RepositoryError> {
+        // A submitter that loses the race for a unique key retries, and then
+        // finds the winner's job.
+        for _ in 0..MAX_INSERT_ATTEMPTS {
+            if let Some(insertion) = self.try_insert_job(job).await? {
+                return Ok(insertion);
+            }
+        }
+
+        Err(RepositoryError::UniqueKeyTaken)
     }
 
     async fn update_job_state(
         &self,
         job_id: Uuid,
+        version: i64,
         from: JobState,
         to: JobState,
         failure: Option<&Failure>,
-    ) -> Result<(), RepositoryError> {
+    ) -> Result<i64, RepositoryError> {
+        from.transition(to, failure)?;
+
         let mut tx = self.pool.begin().await?;
 
-        sqlx::query(
+        let updated = sqlx::query(
             r#"
             UPDATE jobs
             SET
                 state = $1,
+                version = version + 1,
+                lease_owner = NULL,
+                lease_expires_at = NULL,
                 attempt = attempt + CASE WHEN $2 THEN 1 ELSE 0 END,
                 failure_type = $3,
                 failure_reason = $4,
                 updated_at = now()
-            WHERE id = $5 AND state = $6
+            WHERE id = $5 AND state = $6 AND version = $7
+            RETURNING version
             "#
         )
         .bind(state_to_str(to))
-        .bind(to == JobState::Failed)
+        .bind(from == JobState::Running && to == JobState::Failed)
         .bind(failure.map(|f| failure_kind_to_str(f.kind)))
         .bind(failure.map(|f| f.reason.as_str()))
         .bind(job_id)
         .bind(state_to_str(from))
-        .execute(&mut *tx)
-        .await?;
+        .bind(version)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_unique_violation)?;
+
+        let Some(row) = updated else {
+            return Err(lost_update(&mut tx, job_id, from, version).await);
+        };
 
         insert_event(&mut tx, job_id, from, to, "state transition").await?;
         tx.commit().await?;
+        Ok(row.try_get("version")?)
+    }
+
+    async fn requeue_job(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        run_at: DateTime<Utc>,
+    ) -> Result<i64, RepositoryError> {
+        JobState::Failed.transition(JobState::Queued, None)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET
+                state = 'queued',
+                version = version + 1,
+                attempt = 0,
+                run_at = $1,
+                failure_type = NULL,
+                failure_reason = NULL,
+                updated_at = now()
+            WHERE id = $2 AND state = 'failed' AND version = $3
+            RETURNING version
+            "#
+        )
+        .bind(run_at)
+        .bind(job_id)
+        .bind(version)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_unique_violation)?;
+
+        let Some(row) = updated else {
+            return Err(lost_update(&mut tx, job_id, JobState::Failed, version).await);
+        };
+
+        insert_event(&mut tx, job_id, JobState::Failed, JobState::Queued, "requeued").await?;
+        tx.commit().await?;
+        Ok(row.try_get("version")?)
+    }
+
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        result: Option<&serde_json::Value>,
+    ) -> Result<i64, RepositoryError> {
+        JobState::Running.transition(JobState::Succeeded, None)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET
+                state = 'succeeded',
+                version = version + 1,
+                lease_owner = NULL,
+                lease_expires_at = NULL,
+                result = $1,
+                updated_at = now()
+            WHERE id = $2 AND state = 'running' AND lease_owner = $3
+            RETURNING version
+            "#
+        )
+        .bind(result)
+        .bind(job_id)
+        .bind(worker_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = updated else {
+            return Err(lost_lease(&mut tx, job_id, worker_id).await);
+        };
+
+        insert_event(&mut tx, job_id, JobState::Running, JobState::Succeeded, "job succeeded").await?;
+        tx.commit().await?;
+        Ok(row.try_get("version")?)
+    }
+
+    async fn release_job(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        reason: &str,
+    ) -> Result<i64, RepositoryError> {
+        JobState::Running.transition(JobState::Queued, None)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET
+                state = 'queued',
+                version = version + 1,
+                lease_owner = NULL,
+                lease_expires_at = NULL,
+                updated_at = now()
+            WHERE id = $1 AND state = 'running' AND lease_owner = $2
+            RETURNING version
+            "#
+        )
+        .bind(job_id)
+        .bind(worker_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = updated else {
+            return Err(lost_lease(&mut tx, job_id, worker_id).await);
+        };
+
+        insert_event(&mut tx, job_id, JobState::Running, JobState::Queued, reason).await?;
+        tx.commit().await?;
+        Ok(row.try_get("version")?)
+    }
+
+    async fn fail_job(
+        &self,
+        job_id: Uuid,
+        guard: FailGuard<'_>,
+        failure: &Failure,
+        decision: &RetryDecision,
+    ) -> Result<i64, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(version) = fail_running_job(&mut tx, job_id, guard, failure, decision).await? else {
+            return Err(match guard {
+                FailGuard::Lease(worker_id) => lost_lease(&mut tx, job_id, worker_id).await,
+                FailGuard::Version(version) => {
+                    lost_update(&mut tx, job_id, JobState::Running, version).await
+                }
+            });
+        };
+
+        tx.commit().await?;
+        Ok(version)
+    }
+
+    async fn recover_jobs(
+        &self,
+        worker_id: &str,
+        started_at: DateTime<Utc>,
+        failures: &[ForcedFailure],
+        skipped_job_ids: &[Uuid],
+    ) -> Result<RecoveryRun, RepositoryError> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+
+        let mut recovered_job_ids = Vec::new();
+        let mut skipped_job_ids = skipped_job_ids.to_vec();
+
+        for forced in failures {
+            let failed = fail_running_job(
+                &mut tx,
+                forced.job_id,
+                FailGuard::Version(forced.version),
+                &forced.failure,
+                &forced.decision,
+            ).await?;
+
+            match failed {
+                Some(_) => recovered_job_ids.push(forced.job_id),
+                None => skipped_job_ids.push(forced.job_id),
+            }
+        }
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO recovery_runs (
+                id, worker_id, recovered_job_ids, skipped_job_ids, started_at
+            )
+            VALUES ($1,$2,$3,$4,$5)
+            RETURNING finished_at
+            "#
+        )
+        .bind(id)
+        .bind(worker_id)
+        .bind(&recovered_job_ids)
+        .bind(&skipped_job_ids)
+        .bind(started_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(RecoveryRun {
+            id,
+            worker_id: worker_id.to_string(),
+            recovered_job_ids,
+            skipped_job_ids,
+            started_at,
+            finished_at: row.try_get("finished_at")?,
+        })
+    }
+
+    async fn update_job_priority(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        priority: i32,
+    ) -> Result<i64, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET
+                priority = $1,
+                version = version + 1,
+                updated_at = now()
+            WHERE id = $2 AND version = $3
+            RETURNING state, version
+            "#
+        )
+        .bind(priority)
+        .bind(job_id)
+        .bind(version)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = updated else {
+            return Err(version_conflict(&mut tx, job_id, version).await);
+        };
+        let state = str_to_state(row.try_get("state")?);
+
+        insert_event(&mut tx, job_id, state, state, &format!("priority changed to {priority}")).await?;
+        tx.commit().await?;
+        Ok(row.try_get("version")?)
+    }
+
+    async fn update_job_payload(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        payload: &serde_json::Value,
+    ) -> Result<i64, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET
+                payload = $1,
+                version = version + 1,
+                updated_at = now()
+            WHERE id = $2 AND version = $3
+            RETURNING state, version
+            "#
+        )
+        .bind(payload)
+        .bind(job_id)
+        .bind(version)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = updated else {
+            return Err(version_conflict(&mut tx, job_id, version).await);
+        };
+        let state = str_to_state(row.try_get("state")?);
+
+        insert_event(&mut tx, job_id, state, state, "payload replaced").await?;
+        tx.commit().await?;
+        Ok(row.try_get("version")?)
+    }
+}
+
+#[async_trait::async_trait]
+impl RecurringJobRepository for PostgresJobRepository {
+    async fn fetch_recurring_jobs(&self) -> Result<Vec<RecurringJob>, RepositoryError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                id, name, job_type, cron_expression, timezone, payload_template,
+                priority, max_attempts, missed_fire_policy, enabled,
+                last_fire_time, created_at, updated_at
+            FROM recurring_jobs
+            WHERE enabled
+            ORDER BY id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(row_to_recurring_job).collect()
+    }
+
+    async fn insert_recurring_job(&self, definition: &RecurringJob) -> Result<(), RepositoryError> {
+        sqlx::query(
+            r#"
+            INSERT INTO recurring_jobs (
+                id, name, job_type, cron_expression, timezone, payload_template,
+                priority, max_attempts, missed_fire_policy, enabled,
+                last_fire_time, created_at, updated_at
+            )
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
+            "#
+        )
+        .bind(definition.id)
+        .bind(&definition.name)
+        .bind(&definition.job_type)
+        .bind(&definition.cron_expression)
+        .bind(&definition.timezone)
+        .bind(&definition.payload_template)
+        .bind(definition.priority)
+        .bind(definition.max_attempts as i32)
+        .bind(missed_fire_policy_to_str(definition.missed_fire_policy))
+        .bind(definition.enabled)
+        .bind(definition.last_fire_time)
+        .bind(definition.created_at)
+        .bind(definition.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(map_unique_violation)?;
+
+        Ok(())
+    }
+
+    async fn advance_recurring_job(
+        &self,
+        definition_id: Uuid,
+        fire_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query(
+            r#"
+            UPDATE recurring_jobs
+            SET
+                last_fire_time = GREATEST(COALESCE(last_fire_time, $1), $1),
+                updated_at = now()
+            WHERE id = $2
+            "#
+        )
+        .bind(fire_time)
+        .bind(definition_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl JobLogRepository for PostgresJobRepository {
+    async fn append_job_logs(&self, entries: &[JobLogEntry]) -> Result<(), RepositoryError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let job_ids: Vec<Uuid> = entries.iter().map(|entry| entry.job_id).collect();
+        let attempts: Vec<i32> = entries.iter().map(|entry| entry.attempt as i32).collect();
+        let levels: Vec<&str> = entries.iter().map(|entry| log_level_to_str(entry.level)).collect();
+        let messages: Vec<&str> = entries.iter().map(|entry| entry.message.as_str()).collect();
+        let logged_at: Vec<DateTime<Utc>> = entries.iter().map(|entry| entry.logged_at).collect();
+
+        // Lines of jobs deleted in the meantime are dropped, not an error.
+        sqlx::query(
+            r#"
+            INSERT INTO job_logs (job_id, attempt, level, message, logged_at)
+            SELECT l.job_id, l.attempt, l.level, l.message, l.logged_at
+            FROM UNNEST($1::uuid[], $2::int[], $3::text[], $4::text[], $5::timestamptz[])
+                WITH ORDINALITY AS l(job_id, attempt, level, message, logged_at, position)
+            WHERE EXISTS (SELECT 1 FROM jobs WHERE jobs.id = l.job_id)
+            ORDER BY l.position
+            "#
+        )
+        .bind(&job_ids)
+        .bind(&attempts)
+        .bind(&levels)
+        .bind(&messages)
+        .bind(&logged_at)
+        .execute(&self.pool)
+        .await?;
+
         Ok(())
     }
+
+    async fn fetch_job_logs(
+        &self,
+        job_id: Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<JobLogLine>, RepositoryError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT attempt, level, message, logged_at
+            FROM job_logs
+            WHERE job_id = $1
+            ORDER BY id
+            OFFSET $2
+            LIMIT $3
+            "#
+        )
+        .bind(job_id)
+        .bind(offset)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .enumerate()
+            .map(|(index, row)| {
+                Ok(JobLogLine {
+                    offset: offset + index as i64,
+                    attempt: row.try_get::<i32, _>("attempt")? as u32,
+                    level: str_to_log_level(row.try_get("level")?),
+                    message: row.try_get("message")?,
+                    logged_at: row.try_get("logged_at")?,
+                })
+            })
+            .collect()
+    }
 }
 
-fn row_to_job(row: sqlx::postgres::PgRow) -> Result<Job, RepositoryError> {
+fn row_to_job(row: &sqlx::postgres::PgRow) -> Result<Job, RepositoryError> {
     let failure = match row.try_get::<Option<String>, _>("failure_type")? {
         Some(kind) => {
             let reason: String = row.try_get("failure_reason")?;
-            Some(Failure { kind: str_to_failure_kind(&kind), reason })
+            Some(Failure { kind: str_to_failure_kind(&kind), reason, retryable: None })
         }
         None => None,
     };
 
     Ok(Job {
         id: row.try_get("id")?,
+        job_type: row.try_get("job_type")?,
+        queue: row.try_get("queue")?,
+        tenant_id: row.try_get("tenant_id")?,
         payload: row.try_get("payload")?,
         priority: row.try_get("priority")?,
         state: str_to_state(row.try_get("state")?),
+        version: row.try_get("version")?,
         attempt: row.try_get::<i32,_>("attempt")? as u32,
         max_attempts: row.try_get::<i32,_>("max_attempts")? as u32,
+        run_at: row.try_get("run_at")?,
+        timeout: row
+            .try_get::<Option<i64>, _>("timeout_ms")?
+            .map(|ms| Duration::from_millis(ms as u64)),
+        failure_classification: row
+            .try_get::<Option<sqlx::types::Json<FailureClassification>>, _>("failure_classification")?
+            .map(|json| json.0),
+        depends_on: row.try_get("depends_on")?,
+        dependency_failure_policy: str_to_dependency_policy(row.try_get("dependency_failure_policy")?),
+        lease_owner: row.try_get("lease_owner")?,
+        lease_expires_at: row.try_get("lease_expires_at")?,
+        idempotency_key: row.try_get("idempotency_key")?,
+        idempotency_expires_at: row.try_get("idempotency_expires_at")?,
+        unique_key: row.try_get("unique_key")?,
+        debounce: Debounce::Keep,
+        recurring_job_id: row.try_get("recurring_job_id")?,
+        fire_time: row.try_get("fire_time")?,
         failure,
+        result: row.try_get("result")?,
+        progress: row.try_get::<Option<i16>, _>("progress")?.map(|percent| percent as u8),
+        checkpoint: row.try_get("checkpoint")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
 }
 
+fn row_to_recurring_job(row: sqlx::postgres::PgRow) -> Result<RecurringJob, RepositoryError> {
+    Ok(RecurringJob {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        job_type: row.try_get("job_type")?,
+        cron_expression: row.try_get("cron_expression")?,
+        timezone: row.try_get("timezone")?,
+        payload_template: row.try_get("payload_template")?,
+        priority: row.try_get("priority")?,
+        max_attempts: row.try_get::<i32,_>("max_attempts")? as u32,
+        missed_fire_policy: str_to_missed_fire_policy(row.try_get("missed_fire_policy")?),
+        enabled: row.try_get("enabled")?,
+        last_fire_time: row.try_get("last_fire_time")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+/// Records a failed attempt (Running -> Failed) and applies the retry
+/// decision within `tx`. Returns the new version, or `None` if the job is
+/// no longer Running or fails `guard`.
+async fn fail_running_job(
+    tx: &mut Transaction<'_, Postgres>,
+    job_id: Uuid,
+    guard: FailGuard<'_>,
+    failure: &Failure,
+    decision: &RetryDecision,
+) -> Result<Option<i64>, RepositoryError> {
+    JobState::Running.transition(JobState::Failed, Some(failure))?;
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET
+            state = 'failed',
+            version = version + 1,
+            lease_owner = NULL,
+            lease_expires_at = NULL,
+            attempt = attempt + 1,
+            failure_type = $1,
+            failure_reason = $2,
+            updated_at = now()
+        WHERE id = $3
+          AND state = 'running'
+          AND ($4::text IS NULL OR lease_owner = $4)
+          AND ($5::bigint IS NULL OR version = $5)
+        RETURNING version
+        "#
+    )
+    .bind(failure_kind_to_str(failure.kind))
+    .bind(failure.reason.as_str())
+    .bind(job_id)
+    .bind(match guard {
+        FailGuard::Lease(worker_id) => Some(worker_id),
+        FailGuard::Version(_) => None,
+    })
+    .bind(match guard {
+        FailGuard::Version(version) => Some(version),
+        FailGuard::Lease(_) => None,
+    })
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(row) = updated else {
+        return Ok(None);
+    };
+    let mut version: i64 = row.try_get("version")?;
+
+    insert_event(tx, job_id, JobState::Running, JobState::Failed, &failure.reason).await?;
+
+    match decision {
+        RetryDecision::Retry { next_run_at, .. } => {
+            JobState::Failed.transition(JobState::Queued, None)?;
+
+            version = sqlx::query(
+                r#"
+                UPDATE jobs
+                SET
+                    state = 'queued',
+                    version = version + 1,
+                    run_at = $1,
+                    updated_at = now()
+                WHERE id = $2
+                RETURNING version
+                "#
+            )
+            .bind(next_run_at)
+            .bind(job_id)
+            .fetch_one(&mut **tx)
+            .await?
+            .try_get("version")?;
+
+            insert_event(
+                tx, job_id, JobState::Failed, JobState::Queued, &decision.to_string()
+            ).await?;
+        }
+        RetryDecision::Exhausted { .. } | RetryDecision::Terminal { .. } => {
+            insert_event(
+                tx, job_id, JobState::Failed, JobState::Failed, &decision.to_string()
+            ).await?;
+        }
+    }
+
+    Ok(Some(version))
+}
+
+/// The job holding `job`'s idempotency key, if any. Fails with
+/// `IdempotencyConflict` if it was submitted with another job type or
+/// payload.
+async fn idempotent_job(
+    tx: &mut Transaction<'_, Postgres>,
+    job: &Job,
+) -> Result<Option<Job>, RepositoryError> {
+    let key = job.idempotency_key.as_deref().unwrap_or_default();
+
+    let row = sqlx::query(&format!(
+        r#"
+        SELECT {JOB_COLUMNS}
+        FROM jobs
+        WHERE COALESCE(tenant_id, '') = COALESCE($1, '')
+          AND idempotency_key = $2
+        "#
+    ))
+    .bind(job.tenant_id.as_deref())
+    .bind(key)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let existing = row_to_job(&row)?;
+
+    if existing.job_type != job.job_type || existing.payload != job.payload {
+        return Err(RepositoryError::IdempotencyConflict {
+            key: key.to_string(),
+            job_id: existing.id,
+        });
+    }
+
+    Ok(Some(existing))
+}
+
+/// The live job holding `unique_key` in the tenant, locked until `tx` ends.
+async fn live_unique_job(
+    tx: &mut Transaction<'_, Postgres>,
+    tenant_id: Option<&str>,
+    unique_key: &str,
+) -> Result<Option<Job>, RepositoryError> {
+    let row = sqlx::query(&format!(
+        r#"
+        SELECT {JOB_COLUMNS}
+        FROM jobs
+        WHERE COALESCE(tenant_id, '') = COALESCE($1, '')
+          AND unique_key = $2
+          AND state IN ('blocked', 'queued', 'running')
+        FOR UPDATE
+        "#
+    ))
+    .bind(tenant_id)
+    .bind(unique_key)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    row.as_ref().map(row_to_job).transpose()
+}
+
+/// Applies `job`'s debounce mode to `holder`, the live job already holding
+/// its unique key.
+///
+/// Rules:
+/// 1. Only a pending (blocked or queued) holder is changed; a running one
+///    is returned as is.
+/// 2. `ReplacePayload` gives the holder `job`'s payload.
+/// 3. `ExtendRunAt` moves the holder's `run_at` out to `job`'s, never in.
+/// 4. `Keep` returns the holder as is.
+async fn debounce(
+    tx: &mut Transaction<'_, Postgres>,
+    holder: Job,
+    job: &Job,
+) -> Result<Insertion, RepositoryError> {
+    let pending = matches!(holder.state, JobState::Blocked | JobState::Queued);
+
+    let (payload, run_at, reason) = match job.debounce {
+        Debounce::ReplacePayload if pending => {
+            (Some(&job.payload), None, "debounced: payload replaced")
+        }
+        Debounce::ExtendRunAt if pending => (None, Some(job.run_at), "debounced: run_at extended"),
+        _ => return Ok(Insertion::Existing(Box::new(holder))),
+    };
+
+    let row = sqlx::query(&format!(
+        r#"
+        UPDATE jobs
+        SET
+            payload = COALESCE($1, payload),
+            run_at = GREATEST(run_at, COALESCE($2, run_at)),
+            version = version + 1,
+            updated_at = now()
+        WHERE id = $3
+        RETURNING {JOB_COLUMNS}
+        "#
+    ))
+    .bind(payload)
+    .bind(run_at)
+    .bind(holder.id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    insert_event(tx, holder.id, holder.state, holder.state, reason).await?;
+
+    Ok(Insertion::Debounced(Box::new(row_to_job(&row)?)))
+}
+
+/// Explains why a compare-and-swap update matched no row.
+///
+/// A state mismatch wins over a version mismatch, since it is the more
+/// useful of the two to callers.
+async fn lost_update(
+    tx: &mut Transaction<'_, Postgres>,
+    job_id: Uuid,
+    expected_state: JobState,
+    expected_version: i64,
+) -> RepositoryError {
+    match current_state_and_version(tx, job_id).await {
+        Ok(Some((actual, _))) if actual != expected_state => RepositoryError::StaleState {
+            job_id,
+            expected: expected_state,
+            actual,
+        },
+        Ok(Some((_, actual))) => RepositoryError::VersionConflict {
+            job_id,
+            expected: expected_version,
+            actual,
+        },
+        Ok(None) => RepositoryError::NotFound(job_id),
+        Err(err) => RepositoryError::Database(err),
+    }
+}
+
+/// Explains why an update guarded by `worker_id`'s lease matched no row.
+async fn lost_lease(
+    tx: &mut Transaction<'_, Postgres>,
+    job_id: Uuid,
+    worker_id: &str,
+) -> RepositoryError {
+    let row = sqlx::query("SELECT state FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .fetch_optional(&mut **tx)
+        .await;
+
+    match row.and_then(|row| row.map(|row| row.try_get("state")).transpose()) {
+        Ok(Some(state)) => match str_to_state(state) {
+            JobState::Running => RepositoryError::LeaseLost {
+                job_id,
+                worker_id: worker_id.to_string(),
+            },
+            actual => RepositoryError::StaleState {
+                job_id,
+                expected: JobState::Running,
+                actual,
+            },
+        },
+        Ok(None) => RepositoryError::NotFound(job_id),
+        Err(err) => RepositoryError::Database(err),
+    }
+}
+
+async fn version_conflict(
+    tx: &mut Transaction<'_, Postgres>,
+    job_id: Uuid,
+    expected: i64,
+) -> RepositoryError {
+    match current_state_and_version(tx, job_id).await {
+        Ok(Some((_, actual))) => RepositoryError::VersionConflict { job_id, expected, actual },
+        Ok(None) => RepositoryError::NotFound(job_id),
+        Err(err) => RepositoryError::Database(err),
+    }
+}
+
+async fn current_state_and_version(
+    tx: &mut Transaction<'_, Postgres>,
+    job_id: Uuid,
+) -> Result<Option<(JobState, i64)>, sqlx::Error> {
+    let row = sqlx::query("SELECT state, version FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    row.map(|row| Ok((str_to_state(row.try_get("state")?), row.try_get("version")?)))
+        .transpose()
+}
+
+fn map_unique_violation(err: sqlx::Error) -> RepositoryError {
+    match &err {
+        sqlx::Error::Database(db)
+            if db.is_unique_violation() && db.constraint() == Some("idx_jobs_unique_key") =>
+        {
+            RepositoryError::UniqueKeyTaken
+        }
+        sqlx::Error::Database(db) if db.is_unique_violation() => RepositoryError::AlreadyExists,
+        _ => RepositoryError::Database(err),
+    }
+}
+
+fn map_dependency_violation(err: sqlx::Error) -> RepositoryError {
+    match &err {
+        sqlx::Error::Database(db)
+            if db.is_foreign_key_violation()
+                && db.constraint() == Some("job_dependencies_depends_on_fkey") =>
+        {
+            RepositoryError::UnknownDependency
+        }
+        _ => RepositoryError::Database(err),
+    }
+}
+
 fn state_to_str(state: JobState) -> &'static str {
     match state {
+        JobState::Blocked => "blocked",
         JobState::Queued => "queued",
         JobState::Running => "running",
         JobState::Succeeded => "succeeded",
@@ -149,6 +1230,7 @@ fn state_to_str(state: JobState) -> &'static str {
 
 fn str_to_state(value: String) -> JobState {
     match value.as_str() {
+        "blocked" => JobState::Blocked,
         "queued" => JobState::Queued,
         "running" => JobState::Running,
         "succeeded" => JobState::Succeeded,
@@ -175,6 +1257,54 @@ fn str_to_failure_kind(value: &str) -> FailureKind {
     }
 }
 
+fn dependency_policy_to_str(policy: DependencyFailurePolicy) -> &'static str {
+    match policy {
+        DependencyFailurePolicy::Cancel => "cancel",
+        DependencyFailurePolicy::Fail => "fail",
+        DependencyFailurePolicy::RunAnyway => "run_anyway",
+    }
+}
+
+fn str_to_dependency_policy(value: String) -> DependencyFailurePolicy {
+    match value.as_str() {
+        "fail" => DependencyFailurePolicy::Fail,
+        "run_anyway" => DependencyFailurePolicy::RunAnyway,
+        _ => DependencyFailurePolicy::Cancel,
+    }
+}
+
+fn missed_fire_policy_to_str(policy: MissedFirePolicy) -> &'static str {
+    match policy {
+        MissedFirePolicy::Skip => "skip",
+        MissedFirePolicy::FireOnce => "fire_once",
+        MissedFirePolicy::CatchUpAll => "catch_up_all",
+    }
+}
+
+fn str_to_missed_fire_policy(value: String) -> MissedFirePolicy {
+    match value.as_str() {
+        "skip" => MissedFirePolicy::Skip,
+        "catch_up_all" => MissedFirePolicy::CatchUpAll,
+        _ => MissedFirePolicy::FireOnce,
+    }
+}
+
+fn log_level_to_str(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Info => "info",
+        LogLevel::Warn => "warn",
+        LogLevel::Error => "error",
+    }
+}
+
+fn str_to_log_level(value: String) -> LogLevel {
+    match value.as_str() {
+        "warn" => LogLevel::Warn,
+        "error" => LogLevel::Error,
+        _ => LogLevel::Info,
+    }
+}
+
 async fn insert_event(
     tx: &mut Transaction<'_, Postgres>,
     job_id: Uuid,
@@ -1,28 +1,275 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::dependencies::BlockedJob;
 use crate::domain::job::Job;
 use crate::domain::failure::Failure;
-use crate::domain::state::JobState;
+use crate::domain::state::{JobState, StateTransitionError};
+use crate::logs::{JobLogEntry, JobLogLine};
+use crate::recovery::{ForcedFailure, RecoveryRun};
+use crate::recurring::RecurringJob;
+use crate::retry::RetryDecision;
 
 #[async_trait]
 pub trait JobRepository {
-    async fn fetch_queued_jobs(&self) -> Result<Vec<Job>, RepositoryError>;
+    /// Queued jobs that are due at `now`.
+    async fn fetch_queued_jobs(&self, now: DateTime<Utc>) -> Result<Vec<Job>, RepositoryError>;
     async fn fetch_running_jobs(&self) -> Result<Vec<Job>, RepositoryError>;
+    async fn fetch_blocked_jobs(&self) -> Result<Vec<BlockedJob>, RepositoryError>;
 
-    async fn insert_job(&self, job: &Job) -> Result<(), RepositoryError>;
+    /// Fails with `NotFound` if no job has this id.
+    async fn fetch_job(&self, job_id: Uuid) -> Result<Job, RepositoryError>;
 
+    /// Jobs matching `filter`, newest first.
+    async fn list_jobs(&self, filter: &JobFilter) -> Result<Vec<Job>, RepositoryError>;
+
+    /// Atomically moves the given jobs from Queued to Running, leased to
+    /// `worker_id` until `lease_expires_at`, and returns the ones this call
+    /// won, in input order.
+    ///
+    /// Each job is claimed only if it still has the version of the given
+    /// snapshot. Rows locked or already claimed by another orchestrator are
+    /// skipped rather than waited on, so callers never run the same job twice.
+    async fn claim_jobs(
+        &self,
+        jobs: &[Job],
+        worker_id: &str,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<Vec<Job>, RepositoryError>;
+
+    /// Heartbeat: extends the lease of a Running job held by `worker_id`;
+    /// returns the new version.
+    ///
+    /// Like every write made by the worker running a job, this is guarded
+    /// by the lease rather than the version, so admin edits and debounces
+    /// landing meanwhile do not cost the worker its job. Fails with
+    /// `StaleState`, `LeaseLost` or `NotFound` otherwise.
+    async fn renew_lease(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Saves handler progress and/or a checkpoint on a Running job held by
+    /// `worker_id`; `None` keeps the stored value. No event is recorded.
+    /// Guarded like `renew_lease`; returns the new version.
+    async fn save_progress(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        progress: Option<u8>,
+        checkpoint: Option<&[u8]>,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Fails with `AlreadyExists` if the id, or the recurring
+    /// `(recurring_job_id, fire_time)` pair, is already taken, and with
+    /// `UnknownDependency` if a job in `depends_on` does not exist.
+    ///
+    /// With an idempotency key that a job in the same tenant already holds,
+    /// nothing is inserted: the existing job is returned if it has the same
+    /// job type and payload, and `IdempotencyConflict` otherwise. A key is
+    /// held until its `idempotency_expires_at` (`IDEMPOTENCY_TTL_SECS`
+    /// after submission), whatever the holder's state.
+    ///
+    /// With a unique key held by a live job in the same tenant, nothing is
+    /// inserted either; the job's debounce mode is applied to the holder
+    /// instead.
+    async fn insert_job(&self, job: &Job) -> Result<Insertion, RepositoryError>;
+
+    /// Compare-and-swap on `version` and `from`; returns the new version.
+    ///
+    /// Fails with `StaleState`, `VersionConflict` or `NotFound` if the job
+    /// changed underneath the caller; no event is recorded in that case.
     async fn update_job_state(
         &self,
         job_id: Uuid,
+        version: i64,
         from: JobState,
         to: JobState,
         failure: Option<&Failure>,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Moves a Failed job back to Queued with a fresh attempt budget:
+    /// `attempt` is reset to 0 and `run_at` set. Compare-and-swap on
+    /// `version`, like `update_job_state`; returns the new version.
+    async fn requeue_job(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        run_at: DateTime<Utc>,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Records success (Running -> Succeeded) together with the handler's
+    /// result. Guarded like `renew_lease`.
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        result: Option<&serde_json::Value>,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Hands an unfinished Running job back to the queue (Running -> Queued)
+    /// without counting an attempt, recording `reason`. Guarded like
+    /// `renew_lease`.
+    async fn release_job(
+        &self,
+        job_id: Uuid,
+        worker_id: &str,
+        reason: &str,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Records a failed attempt (Running -> Failed) and applies the retry
+    /// decision (Failed -> Queued, or stay Failed) in a single transaction.
+    ///
+    /// Fails with `StaleState`, `NotFound`, or per `guard` with `LeaseLost`
+    /// or `VersionConflict`; nothing is written in that case.
+    async fn fail_job(
+        &self,
+        job_id: Uuid,
+        guard: FailGuard<'_>,
+        failure: &Failure,
+        decision: &RetryDecision,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Startup recovery: applies every forced failure like `fail_job` and
+    /// records the run in `recovery_runs`, all in one transaction.
+    ///
+    /// Jobs that changed since they were read are moved to the skipped list
+    /// rather than failing the whole run.
+    async fn recover_jobs(
+        &self,
+        worker_id: &str,
+        started_at: DateTime<Utc>,
+        failures: &[ForcedFailure],
+        skipped_job_ids: &[Uuid],
+    ) -> Result<RecoveryRun, RepositoryError>;
+
+    /// Admin edit; compare-and-swap on `version`, returns the new version.
+    async fn update_job_priority(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        priority: i32,
+    ) -> Result<i64, RepositoryError>;
+
+    /// Admin edit; compare-and-swap on `version`, returns the new version.
+    /// The new payload is seen by the next attempt.
+    async fn update_job_payload(
+        &self,
+        job_id: Uuid,
+        version: i64,
+        payload: &serde_json::Value,
+    ) -> Result<i64, RepositoryError>;
+}
+
+/// What `JobRepository::fail_job` requires of the Running job.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FailGuard<'a> {
+    /// Leased to this worker, at any version; for the worker running it.
+    Lease(&'a str),
+    /// Still at this version; for the reaper, which must lose to a
+    /// heartbeat that lands first.
+    Version(i64),
+}
+
+/// Result of `JobRepository::insert_job`.
+#[derive(Debug, Clone)]
+pub enum Insertion {
+    Created,
+    /// An earlier submission with the same idempotency or unique key,
+    /// unchanged.
+    Existing(Box<Job>),
+    /// The pending job holding the unique key, updated per the debounce
+    /// mode.
+    Debounced(Box<Job>),
+}
+
+/// Criteria for `JobRepository::list_jobs`; `None` fields match any job.
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter {
+    pub state: Option<JobState>,
+    pub job_type: Option<String>,
+    pub queue: Option<String>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[async_trait]
+pub trait RecurringJobRepository {
+    async fn fetch_recurring_jobs(&self) -> Result<Vec<RecurringJob>, RepositoryError>;
+
+    async fn insert_recurring_job(&self, definition: &RecurringJob) -> Result<(), RepositoryError>;
+
+    /// Moves `last_fire_time` forward to `fire_time`; never moves it back.
+    async fn advance_recurring_job(
+        &self,
+        definition_id: Uuid,
+        fire_time: DateTime<Utc>,
     ) -> Result<(), RepositoryError>;
 }
 
+#[async_trait]
+pub trait JobLogRepository {
+    async fn append_job_logs(&self, entries: &[JobLogEntry]) -> Result<(), RepositoryError>;
+
+    /// Up to `limit` lines of the job's log, skipping the first `offset`,
+    /// oldest first.
+    async fn fetch_job_logs(
+        &self,
+        job_id: Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<JobLogLine>, RepositoryError>;
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum RepositoryError {
     #[error("database error: {0}")]
     Database(#[from] sqlx::Error),
+
+    #[error("record already exists")]
+    AlreadyExists,
+
+    #[error("job {0} not found")]
+    NotFound(Uuid),
+
+    /// A state-guarded update found the job in a different state; nothing
+    /// was written.
+    #[error("job {job_id} is {actual:?}, expected {expected:?}")]
+    StaleState {
+        job_id: Uuid,
+        expected: JobState,
+        actual: JobState,
+    },
+
+    /// The job was modified since the caller read it; nothing was written.
+    #[error("job {job_id} is at version {actual}, expected {expected}")]
+    VersionConflict {
+        job_id: Uuid,
+        expected: i64,
+        actual: i64,
+    },
+
+    /// The job is Running under another worker's lease, or none; nothing
+    /// was written.
+    #[error("job {job_id} is no longer leased to {worker_id}")]
+    LeaseLost { job_id: Uuid, worker_id: String },
+
+    /// The idempotency key is held by a job with a different type or
+    /// payload; nothing was written.
+    #[error("idempotency key '{key}' is already used by job {job_id} with a different payload")]
+    IdempotencyConflict { key: String, job_id: Uuid },
+
+    /// Another live job holds the same unique key; nothing was written.
+    #[error("another live job holds the same unique key")]
+    UniqueKeyTaken,
+
+    /// A job the new job depends on does not exist; nothing was written.
+    #[error("depends_on names a job that does not exist")]
+    UnknownDependency,
+
+    #[error(transparent)]
+    InvalidTransition(#[from] StateTransitionError),
 }
@@ -0,0 +1,370 @@
+//! Tests of the Postgres repository. They need a database and are ignored by
+//! default; run them with `DATABASE_URL=... cargo test -- --ignored`.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::json;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Executor, PgPool};
+use uuid::Uuid;
+
+use crate::domain::job::{Debounce, Job};
+use crate::domain::state::JobState;
+use crate::storage::repository::{Insertion, JobRepository, RepositoryError};
+use crate::storage::PostgresJobRepository;
+
+/// A pool over a fresh schema of the database at `DATABASE_URL`, with every
+/// migration applied. The schema is left behind for inspection.
+async fn database() -> PgPool {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must point at Postgres");
+    let schema = format!("test_{}", Uuid::new_v4().simple());
+
+    let admin = PgPool::connect(&url).await.unwrap();
+    admin
+        .execute(format!("CREATE SCHEMA {schema}").as_str())
+        .await
+        .unwrap();
+    admin.close().await;
+
+    let options = PgConnectOptions::from_str(&url)
+        .unwrap()
+        .options([("search_path", schema.as_str())]);
+    let pool = PgPoolOptions::new()
+        .max_connections(8)
+        .connect_with(options)
+        .await
+        .unwrap();
+
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+    let mut migrations: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    migrations.sort();
+    for migration in migrations {
+        let sql = std::fs::read_to_string(&migration).unwrap();
+        pool.execute(sql.as_str()).await.unwrap();
+    }
+
+    pool
+}
+
+async fn repository() -> PostgresJobRepository {
+    PostgresJobRepository::new(database().await)
+}
+
+fn at(secs: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+}
+
+fn submitted(payload: serde_json::Value, now: DateTime<Utc>) -> Job {
+    Job::new(Uuid::new_v4(), payload, now)
+}
+
+fn keyed(payload: serde_json::Value, now: DateTime<Utc>) -> Job {
+    submitted(payload, now).with_idempotency_key("order-1", now + Duration::hours(1))
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn idempotent_resubmit_returns_the_existing_job() {
+    let repo = repository().await;
+    let first = keyed(json!({ "n": 1 }), at(0));
+    assert!(matches!(
+        repo.insert_job(&first).await.unwrap(),
+        Insertion::Created
+    ));
+
+    let again = keyed(json!({ "n": 1 }), at(60));
+    match repo.insert_job(&again).await.unwrap() {
+        Insertion::Existing(job) => assert_eq!(job.id, first.id),
+        other => panic!("expected the existing job, got {other:?}"),
+    }
+    assert!(matches!(
+        repo.fetch_job(again.id).await,
+        Err(RepositoryError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn idempotency_key_reused_for_another_job_conflicts() {
+    let repo = repository().await;
+    let first = keyed(json!({ "n": 1 }), at(0));
+    repo.insert_job(&first).await.unwrap();
+
+    let other_payload = keyed(json!({ "n": 2 }), at(60));
+    let other_type = keyed(json!({ "n": 1 }), at(60)).with_job_type("report");
+
+    for job in [other_payload, other_type] {
+        match repo.insert_job(&job).await {
+            Err(RepositoryError::IdempotencyConflict { key, job_id }) => {
+                assert_eq!(key, "order-1");
+                assert_eq!(job_id, first.id);
+            }
+            other => panic!("expected a conflict, got {other:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn idempotency_key_is_held_whatever_the_holders_state() {
+    let repo = repository().await;
+    let first = keyed(json!({ "n": 1 }), at(0));
+    repo.insert_job(&first).await.unwrap();
+    repo.update_job_state(
+        first.id,
+        first.version,
+        JobState::Queued,
+        JobState::Cancelled,
+        None,
+    )
+    .await
+    .unwrap();
+
+    let again = keyed(json!({ "n": 1 }), at(60));
+    match repo.insert_job(&again).await.unwrap() {
+        Insertion::Existing(job) => assert_eq!(job.id, first.id),
+        other => panic!("expected the existing job, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn expired_idempotency_key_can_be_reused() {
+    let repo = repository().await;
+    let first = keyed(json!({ "n": 1 }), at(0));
+    repo.insert_job(&first).await.unwrap();
+
+    let later = keyed(json!({ "n": 2 }), at(0) + Duration::hours(2));
+    assert!(matches!(
+        repo.insert_job(&later).await.unwrap(),
+        Insertion::Created
+    ));
+
+    let released = repo.fetch_job(first.id).await.unwrap();
+    assert_eq!(released.idempotency_key, None);
+    let taken = repo.fetch_job(later.id).await.unwrap();
+    assert_eq!(taken.idempotency_key.as_deref(), Some("order-1"));
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn idempotency_keys_are_scoped_per_tenant() {
+    let repo = repository().await;
+    let untenanted = keyed(json!({ "n": 1 }), at(0));
+    let acme = keyed(json!({ "n": 2 }), at(0)).with_tenant("acme");
+    let globex = keyed(json!({ "n": 3 }), at(0)).with_tenant("globex");
+
+    for job in [&untenanted, &acme, &globex] {
+        assert!(matches!(
+            repo.insert_job(job).await.unwrap(),
+            Insertion::Created
+        ));
+    }
+
+    let acme_again = keyed(json!({ "n": 2 }), at(60)).with_tenant("acme");
+    match repo.insert_job(&acme_again).await.unwrap() {
+        Insertion::Existing(job) => assert_eq!(job.id, acme.id),
+        other => panic!("expected acme's job, got {other:?}"),
+    }
+}
+
+fn unique(payload: serde_json::Value, now: DateTime<Utc>, debounce: Debounce) -> Job {
+    submitted(payload, now).with_unique_key("reindex", debounce)
+}
+
+/// Ids of the live jobs holding the `reindex` unique key.
+async fn live_holders(pool: &PgPool) -> Vec<Uuid> {
+    sqlx::query_scalar(
+        "SELECT id FROM jobs WHERE unique_key = 'reindex' AND state IN ('blocked', 'queued', 'running')",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn second_live_submit_with_a_unique_key_is_deduped() {
+    let pool = database().await;
+    let repo = PostgresJobRepository::new(pool.clone());
+    let first = unique(json!({ "n": 1 }), at(0), Debounce::Keep);
+    repo.insert_job(&first).await.unwrap();
+
+    let second = unique(json!({ "n": 2 }), at(60), Debounce::Keep);
+    match repo.insert_job(&second).await.unwrap() {
+        Insertion::Existing(job) => {
+            assert_eq!(job.id, first.id);
+            assert_eq!(job.payload, json!({ "n": 1 }));
+        }
+        other => panic!("expected the holder, got {other:?}"),
+    }
+    assert_eq!(live_holders(&pool).await, vec![first.id]);
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn replace_payload_swaps_the_pending_holders_payload() {
+    let repo = repository().await;
+    let first = unique(json!({ "n": 1 }), at(0), Debounce::ReplacePayload);
+    repo.insert_job(&first).await.unwrap();
+
+    let second = unique(json!({ "n": 2 }), at(60), Debounce::ReplacePayload);
+    match repo.insert_job(&second).await.unwrap() {
+        Insertion::Debounced(job) => {
+            assert_eq!(job.id, first.id);
+            assert_eq!(job.payload, json!({ "n": 2 }));
+            assert_eq!(job.version, first.version + 1);
+        }
+        other => panic!("expected a debounce, got {other:?}"),
+    }
+    assert_eq!(
+        repo.fetch_job(first.id).await.unwrap().payload,
+        json!({ "n": 2 })
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn extend_run_at_only_moves_run_at_later() {
+    let repo = repository().await;
+    let mut first = unique(json!({}), at(0), Debounce::ExtendRunAt);
+    first.run_at = at(100);
+    repo.insert_job(&first).await.unwrap();
+
+    let mut later = unique(json!({}), at(10), Debounce::ExtendRunAt);
+    later.run_at = at(200);
+    match repo.insert_job(&later).await.unwrap() {
+        Insertion::Debounced(job) => assert_eq!(job.run_at, at(200)),
+        other => panic!("expected a debounce, got {other:?}"),
+    }
+
+    let mut earlier = unique(json!({}), at(20), Debounce::ExtendRunAt);
+    earlier.run_at = at(50);
+    match repo.insert_job(&earlier).await.unwrap() {
+        Insertion::Debounced(job) => assert_eq!(job.run_at, at(200)),
+        other => panic!("expected a debounce, got {other:?}"),
+    }
+    assert_eq!(repo.fetch_job(first.id).await.unwrap().run_at, at(200));
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn running_holder_is_left_unchanged() {
+    let repo = repository().await;
+    let first = unique(json!({ "n": 1 }), at(0), Debounce::ReplacePayload);
+    repo.insert_job(&first).await.unwrap();
+    let claimed = repo
+        .claim_jobs(std::slice::from_ref(&first), "worker-a", at(30))
+        .await
+        .unwrap();
+    assert_eq!(claimed.len(), 1);
+
+    let mut second = unique(json!({ "n": 2 }), at(60), Debounce::ReplacePayload);
+    second.run_at = at(600);
+    match repo.insert_job(&second).await.unwrap() {
+        Insertion::Existing(job) => {
+            assert_eq!(job.state, JobState::Running);
+            assert_eq!(job.payload, json!({ "n": 1 }));
+            assert_eq!(job.version, claimed[0].version);
+        }
+        other => panic!("expected the running holder, got {other:?}"),
+    }
+
+    let mut extend = unique(json!({}), at(60), Debounce::ExtendRunAt);
+    extend.run_at = at(600);
+    assert!(matches!(
+        repo.insert_job(&extend).await.unwrap(),
+        Insertion::Existing(_)
+    ));
+    assert_eq!(repo.fetch_job(first.id).await.unwrap().run_at, first.run_at);
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn unique_key_frees_up_once_the_holder_is_terminal() {
+    let pool = database().await;
+    let repo = PostgresJobRepository::new(pool.clone());
+    let first = unique(json!({}), at(0), Debounce::Keep);
+    repo.insert_job(&first).await.unwrap();
+    repo.update_job_state(
+        first.id,
+        first.version,
+        JobState::Queued,
+        JobState::Cancelled,
+        None,
+    )
+    .await
+    .unwrap();
+
+    let second = unique(json!({}), at(60), Debounce::Keep);
+    assert!(matches!(
+        repo.insert_job(&second).await.unwrap(),
+        Insertion::Created
+    ));
+    assert_eq!(live_holders(&pool).await, vec![second.id]);
+}
+
+#[tokio::test]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn submitter_that_loses_the_unique_key_race_returns_the_winner() {
+    let pool = database().await;
+    let repo = PostgresJobRepository::new(pool.clone());
+
+    // A rival submitter has inserted a holder but not committed yet, so the
+    // lookup below misses it and the insert waits on idx_jobs_unique_key.
+    let rival = Uuid::new_v4();
+    let mut rival_tx = pool.begin().await.unwrap();
+    sqlx::query(
+        "INSERT INTO jobs (id, payload, state, unique_key) VALUES ($1, '{}', 'queued', 'reindex')",
+    )
+    .bind(rival)
+    .execute(&mut *rival_tx)
+    .await
+    .unwrap();
+
+    let job = unique(json!({}), at(0), Debounce::Keep);
+    let submit = tokio::spawn(async move { repo.insert_job(&job).await });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(!submit.is_finished());
+
+    rival_tx.commit().await.unwrap();
+    match submit.await.unwrap().unwrap() {
+        Insertion::Existing(job) => assert_eq!(job.id, rival),
+        other => panic!("expected the rival's job, got {other:?}"),
+    }
+    assert_eq!(live_holders(&pool).await, vec![rival]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[ignore = "needs Postgres: set DATABASE_URL"]
+async fn concurrent_submitters_create_one_live_job() {
+    let pool = database().await;
+    let repo = Arc::new(PostgresJobRepository::new(pool.clone()));
+
+    let submits: Vec<_> = (0..8)
+        .map(|n| {
+            let repo = repo.clone();
+            let job = unique(json!({ "n": n }), at(n), Debounce::Keep);
+            tokio::spawn(async move { (job.id, repo.insert_job(&job).await.unwrap()) })
+        })
+        .collect();
+
+    let mut created = Vec::new();
+    let mut existing = Vec::new();
+    for submit in submits {
+        match submit.await.unwrap() {
+            (id, Insertion::Created) => created.push(id),
+            (_, Insertion::Existing(job)) => existing.push(job.id),
+            (_, other) => panic!("unexpected {other:?}"),
+        }
+    }
+
+    assert_eq!(created.len(), 1);
+    assert!(existing.iter().all(|id| *id == created[0]));
+    assert_eq!(live_holders(&pool).await, created);
+}